@@ -1,5 +1,6 @@
 use std::cmp::{max, min};
 use std::fs;
+use std::io::{self, BufRead, BufReader};
 
 struct Cache(Vec<Vec<usize>>);
 
@@ -132,6 +133,59 @@ impl Generator {
 
         (values, sum)
     }
+
+    fn stream<R: BufRead>(reader: R, counts: &[usize]) -> GeneratorStream<R> {
+        GeneratorStream {
+            lines: reader.lines(),
+            counts: counts.to_vec(),
+        }
+    }
+}
+
+// Reads one bank per line and yields its maxima for each requested count,
+// so only the current line is ever held in memory.
+struct GeneratorStream<R: BufRead> {
+    lines: io::Lines<R>,
+    counts: Vec<usize>,
+}
+
+impl<R: BufRead> Iterator for GeneratorStream<R> {
+    type Item = io::Result<Vec<usize>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(l) => l,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let bank = Bank::from(line.as_str());
+
+            return Some(Ok(self
+                .counts
+                .iter()
+                .map(|&c| bank.max_of_count_batteries(c))
+                .collect()));
+        }
+    }
+}
+
+impl<R: BufRead> GeneratorStream<R> {
+    fn sums(self) -> io::Result<Vec<usize>> {
+        let mut sums = vec![0; self.counts.len()];
+
+        for maxima in self {
+            for (sum, value) in sums.iter_mut().zip(maxima?) {
+                *sum += value;
+            }
+        }
+
+        Ok(sums)
+    }
 }
 
 fn main() {
@@ -148,10 +202,39 @@ fn main() {
         sample_generator.max_count_batteries(12).1
     );
 
-    let input = fs::read_to_string("input.txt").unwrap();
+    let input = BufReader::new(fs::File::open("input.txt").unwrap());
+
+    let sums = Generator::stream(input, &[2, 12]).sums().unwrap();
+
+    println!("Input Part 1: {}", sums[0]);
+    println!("Input Part 2: {}", sums[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "987654321111111\n811111111111119\n234234234234278\n818181911112111\n";
+
+    #[test]
+    fn stream_matches_generator() {
+        let generator = Generator::from(SAMPLE);
+
+        let sums = Generator::stream(SAMPLE.as_bytes(), &[2, 12])
+            .sums()
+            .unwrap();
 
-    let generator = Generator::from(input.as_str());
+        assert_eq!(sums[0], generator.max_count_batteries(2).1);
+        assert_eq!(sums[1], generator.max_count_batteries(12).1);
+        assert_eq!(sums, vec![357, 3121910778619]);
+    }
+
+    #[test]
+    fn stream_per_bank() {
+        let maxima = Generator::stream(SAMPLE.as_bytes(), &[2])
+            .map(|m| m.unwrap()[0])
+            .collect::<Vec<_>>();
 
-    println!("Input Part 1: {}", generator.max_count_batteries(2).1);
-    println!("Input Part 2: {}", generator.max_count_batteries(12).1);
+        assert_eq!(maxima, vec![98, 89, 78, 92]);
+    }
 }
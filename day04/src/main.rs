@@ -1,10 +1,13 @@
 use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Tile {
     Empty,
     Roll,
     RollGrabbable,
+    Wall,
 }
 
 impl std::fmt::Debug for Tile {
@@ -13,10 +16,22 @@ impl std::fmt::Debug for Tile {
             Self::Empty => ".",
             Self::Roll => "@",
             Self::RollGrabbable => "x",
+            Self::Wall => "#",
         })
     }
 }
 
+impl Tile {
+    fn colour(&self) -> [u8; 3] {
+        match self {
+            Self::Empty => [255, 255, 255],
+            Self::Roll => [60, 60, 60],
+            Self::RollGrabbable => [220, 40, 40],
+            Self::Wall => [0, 0, 0],
+        }
+    }
+}
+
 impl From<char> for Tile {
     fn from(value: char) -> Self {
         match value {
@@ -27,13 +42,88 @@ impl From<char> for Tile {
     }
 }
 
+const ADJACENT: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+];
+
+const VON_NEUMANN: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+const GRAB_THRESHOLD: usize = 4;
+
+#[derive(Clone, Debug)]
+enum Neighbourhood {
+    VonNeumann,
+    Moore,
+    Radius(usize),
+    Custom(Vec<(isize, isize)>),
+}
+
+impl Neighbourhood {
+    fn offsets(&self) -> Vec<(isize, isize)> {
+        match self {
+            Self::VonNeumann => VON_NEUMANN.to_vec(),
+            Self::Moore => ADJACENT.to_vec(),
+            Self::Radius(r) => {
+                let r = *r as isize;
+                (-r..=r)
+                    .flat_map(|x| (-r..=r).map(move |y| (x, y)))
+                    .filter(|&o| o != (0, 0))
+                    .collect()
+            }
+            Self::Custom(offsets) => offsets.clone(),
+        }
+    }
+}
+
+// A roll is grabbable when fewer than `threshold` of the cells at
+// `offsets` around it are occupied.
+#[derive(Clone, Debug)]
+struct Rule {
+    offsets: Vec<(isize, isize)>,
+    threshold: usize,
+}
+
+impl Rule {
+    fn new(neighbourhood: Neighbourhood, threshold: usize) -> Self {
+        Self {
+            offsets: neighbourhood.offsets(),
+            threshold,
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::new(Neighbourhood::Moore, GRAB_THRESHOLD)
+    }
+}
+
+// What `Warehouse::get` sees past the edge of the grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+enum Edge {
+    #[default]
+    Open,
+    Walls,
+    Torus,
+}
+
 #[derive(Clone)]
-struct Warehouse(Vec<Vec<Tile>>);
+struct Warehouse {
+    grid: Vec<Vec<Tile>>,
+    edge: Edge,
+}
 
 impl std::fmt::Debug for Warehouse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(
-            self.0
+            self.grid
                 .iter()
                 .map(|l| {
                     l.iter()
@@ -50,22 +140,29 @@ impl std::fmt::Debug for Warehouse {
 
 impl From<&str> for Warehouse {
     fn from(value: &str) -> Self {
-        Self(
-            value
+        Self {
+            grid: value
                 .split("\n")
                 .filter(|l| !l.is_empty())
-                .map(|l| l.chars().map(|c| Tile::from(c)).collect::<Vec<_>>())
+                .map(|l| l.chars().map(Tile::from).collect::<Vec<_>>())
                 .collect::<Vec<_>>(),
-        )
+            edge: Edge::default(),
+        }
     }
 }
 
 impl Warehouse {
-    fn get(&self, row: isize, col: isize) -> Option<Tile> {
-        let get_owned = |v: &Vec<Tile>, c: usize| match v.get(c) {
-            Some(t) => Some(*t),
-            None => None,
-        };
+    fn with_edge(self, edge: Edge) -> Self {
+        Self { edge, ..self }
+    }
+
+    // Grid position for `(row, col)`, or `None` if it falls off the grid
+    fn position(&self, row: isize, col: isize) -> Option<(usize, usize)> {
+        if self.edge == Edge::Torus {
+            let row = row.rem_euclid(self.grid.len() as isize) as usize;
+            let col = col.rem_euclid(self.grid[row].len() as isize) as usize;
+            return Some((row, col));
+        }
 
         let row_negative = row < 0;
         let col_negative = col < 0;
@@ -73,71 +170,61 @@ impl Warehouse {
         match (row_negative, col_negative) {
             (true, _) => None,
             (_, true) => None,
-            _ => match self.0.get(row as usize) {
-                None => None,
-                Some(v) => get_owned(v, col as usize),
-            },
+            _ => self
+                .grid
+                .get(row as usize)
+                .filter(|v| (col as usize) < v.len())
+                .map(|_| (row as usize, col as usize)),
+        }
+    }
+
+    fn get(&self, row: isize, col: isize) -> Option<Tile> {
+        match self.position(row, col) {
+            Some((i, j)) => Some(self.grid[i][j]),
+            None if self.edge == Edge::Walls => Some(Tile::Wall),
+            None => None,
         }
     }
 
+    fn count_adjacent(&self, row: usize, col: usize, rule: &Rule) -> usize {
+        rule.offsets
+            .iter()
+            .filter_map(|(x, y)| self.get(row as isize + x, col as isize + y))
+            .filter(|v| *v != Tile::Empty)
+            .count()
+    }
+
     fn find_grabbable(self) -> (Self, usize) {
-        const ADJACENT: [(isize, isize); 8] = [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, 1),
-            (1, 1),
-            (1, 0),
-            (1, -1),
-            (0, -1),
-        ];
+        self.find_grabbable_with(&Rule::default())
+    }
 
+    fn find_grabbable_with(mut self, rule: &Rule) -> (Self, usize) {
         let mut available_rolls = Vec::new();
 
-        for (i, row) in self.0.iter().enumerate() {
+        for (i, row) in self.grid.iter().enumerate() {
             for (j, &tile) in row.iter().enumerate() {
                 if tile == Tile::Empty {
                     continue;
                 }
 
-                let count_adjacent = ADJACENT
-                    .iter()
-                    .filter_map(|(x, y)| self.get(i as isize + x, j as isize + y))
-                    .filter(|v| *v != Tile::Empty)
-                    .count();
-
-                if count_adjacent < 4 {
+                if self.count_adjacent(i, j, rule) < rule.threshold {
                     available_rolls.push((i, j));
                 }
             }
         }
 
-        (
-            Self(
-                self.0
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, v)| {
-                        v.into_iter()
-                            .enumerate()
-                            .map(|(j, t)| {
-                                if available_rolls.contains(&(i, j)) {
-                                    Tile::RollGrabbable
-                                } else {
-                                    t
-                                }
-                            })
-                            .collect()
-                    })
-                    .collect(),
-            ),
-            available_rolls.len(),
-        )
+        for &(i, j) in available_rolls.iter() {
+            self.grid[i][j] = Tile::RollGrabbable;
+        }
+
+        (self, available_rolls.len())
     }
 
+    #[cfg(test)]
     fn remove_grabbable(self) -> Self {
-        Self(
-            self.0
+        Self {
+            grid: self
+                .grid
                 .into_iter()
                 .map(|v| {
                     v.into_iter()
@@ -151,41 +238,573 @@ impl Warehouse {
                         .collect()
                 })
                 .collect(),
-        )
+            edge: self.edge,
+        }
     }
 
     fn remove_all(self) -> (Self, usize) {
-        let (mut warehouse, mut removeable) = self.find_grabbable();
+        self.remove_all_with(&Rule::default())
+    }
+
+    fn remove_all_with(self, rule: &Rule) -> (Self, usize) {
+        let (warehouse, rounds) = self.peel(rule);
+
+        (warehouse, rounds.iter().map(|r| r.len()).sum())
+    }
+
+    fn history(&self, rule: &Rule) -> History {
+        let (_, rounds) = self.clone().peel(rule);
+
+        History {
+            initial: self.clone(),
+            rounds,
+        }
+    }
+
+    // Round-by-round peeling without rescanning the grid: every roll keeps a
+    // count of occupied neighbours, and removing a roll only revisits the
+    // cells around it. A roll joins the next round's queue the moment its
+    // count drops below the threshold, so rounds match `find_grabbable`.
+    fn peel(mut self, rule: &Rule) -> (Self, Vec<Vec<(usize, usize)>>) {
+        let mut counts = self
+            .grid
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(j, &tile)| match tile {
+                        Tile::Empty => 0,
+                        _ => self.count_adjacent(i, j, rule),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut queued = counts
+            .iter()
+            .map(|r| vec![false; r.len()])
+            .collect::<Vec<_>>();
+        let mut round = Vec::new();
+
+        for (i, row) in self.grid.iter().enumerate() {
+            for (j, &tile) in row.iter().enumerate() {
+                if tile != Tile::Empty && counts[i][j] < rule.threshold {
+                    queued[i][j] = true;
+                    round.push((i, j));
+                }
+            }
+        }
+
+        let mut rounds = Vec::new();
+
+        while !round.is_empty() {
+            for &(i, j) in round.iter() {
+                self.grid[i][j] = Tile::Empty;
+            }
+
+            let mut next = Vec::new();
+
+            // A removed cell is a neighbour of every cell it is offset from,
+            // so walk the offsets backwards in case the rule is asymmetric.
+            for &(i, j) in round.iter() {
+                for &(x, y) in rule.offsets.iter() {
+                    let Some((ni, nj)) = self.position(i as isize - x, j as isize - y) else {
+                        continue;
+                    };
+
+                    if self.grid[ni][nj] == Tile::Empty {
+                        continue;
+                    }
+
+                    counts[ni][nj] -= 1;
+
+                    if !queued[ni][nj] && counts[ni][nj] < rule.threshold {
+                        queued[ni][nj] = true;
+                        next.push((ni, nj));
+                    }
+                }
+            }
+
+            rounds.push(round);
+            round = next;
+        }
+
+        (self, rounds)
+    }
+
+    #[cfg(test)]
+    fn remove_all_rescan(self, rule: &Rule) -> (Self, usize) {
+        let (mut warehouse, mut removeable) = self.find_grabbable_with(rule);
         let mut total_removed = 0;
 
         while removeable > 0 {
             warehouse = warehouse.remove_grabbable();
             total_removed += removeable;
-            (warehouse, removeable) = warehouse.find_grabbable();
+            (warehouse, removeable) = warehouse.find_grabbable_with(rule);
         }
 
         (warehouse, total_removed)
     }
 }
 
+// Each round reads one grid and writes the next into a second buffer, so
+// rows can be split across threads without any locking.
+#[cfg(feature = "parallel")]
+impl Warehouse {
+    fn step_parallel(&self, next: &mut Self, rule: &Rule, threads: usize) -> usize {
+        let rows_per_thread = self.grid.len().div_ceil(threads.max(1)).max(1);
+
+        std::thread::scope(|scope| {
+            let handles = next
+                .grid
+                .chunks_mut(rows_per_thread)
+                .enumerate()
+                .map(|(n, chunk)| {
+                    scope.spawn(move || {
+                        let mut removed = 0;
+
+                        for (offset, row) in chunk.iter_mut().enumerate() {
+                            let i = n * rows_per_thread + offset;
+
+                            for (j, tile) in row.iter_mut().enumerate() {
+                                *tile = self.grid[i][j];
+
+                                if *tile != Tile::Empty
+                                    && self.count_adjacent(i, j, rule) < rule.threshold
+                                {
+                                    *tile = Tile::Empty;
+                                    removed += 1;
+                                }
+                            }
+                        }
+
+                        removed
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        })
+    }
+
+    fn remove_all_parallel(self, rule: &Rule, threads: usize) -> (Self, usize) {
+        let mut current = self;
+        let mut next = current.clone();
+        let mut total_removed = 0;
+
+        loop {
+            let removed = current.step_parallel(&mut next, rule, threads);
+
+            if removed == 0 {
+                return (current, total_removed);
+            }
+
+            total_removed += removed;
+            std::mem::swap(&mut current, &mut next);
+        }
+    }
+}
+
+// Cells removed in each round of `remove_all`, starting from `initial`
+struct History {
+    initial: Warehouse,
+    rounds: Vec<Vec<(usize, usize)>>,
+}
+
+impl History {
+    // One frame per round with that round's removals marked grabbable,
+    // followed by the final state.
+    fn frames(&self) -> Vec<Warehouse> {
+        let mut state = self.initial.clone();
+        let mut frames = Vec::with_capacity(self.rounds.len() + 1);
+
+        for round in self.rounds.iter() {
+            for &(i, j) in round.iter() {
+                state.grid[i][j] = Tile::RollGrabbable;
+            }
+
+            frames.push(state.clone());
+
+            for &(i, j) in round.iter() {
+                state.grid[i][j] = Tile::Empty;
+            }
+        }
+
+        frames.push(state);
+        frames
+    }
+
+    fn write_ascii<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let frames = self.frames();
+        let last = frames.len() - 1;
+
+        for (n, frame) in frames.iter().enumerate() {
+            if n == last {
+                writeln!(out, "Final")?;
+            } else {
+                writeln!(out, "Round {} ({} removed)", n + 1, self.rounds[n].len())?;
+            }
+            writeln!(out, "{:?}\n", frame)?;
+        }
+
+        Ok(())
+    }
+
+    // Writes `frame_000.ppm`, `frame_001.ppm`, ... with each tile drawn as a
+    // `scale` x `scale` block.
+    fn write_ppm_series(&self, dir: &Path, scale: usize) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+
+        for (n, frame) in self.frames().iter().enumerate() {
+            let mut file =
+                io::BufWriter::new(fs::File::create(dir.join(format!("frame_{:03}.ppm", n)))?);
+
+            write_ppm(frame, scale, &mut file)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_ppm<W: Write>(warehouse: &Warehouse, scale: usize, out: &mut W) -> io::Result<()> {
+    let height = warehouse.grid.len();
+    let width = warehouse.grid.iter().map(|r| r.len()).max().unwrap_or(0);
+
+    write!(out, "P6\n{} {}\n255\n", width * scale, height * scale)?;
+
+    for row in warehouse.grid.iter() {
+        let line = (0..width)
+            .flat_map(|j| {
+                let colour = row.get(j).unwrap_or(&Tile::Empty).colour();
+                std::iter::repeat_n(colour, scale)
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+
+        for _ in 0..scale {
+            out.write_all(&line)?;
+        }
+    }
+
+    Ok(())
+}
+
 fn main() {
     let sample_input = fs::read_to_string("sample_input.txt").unwrap();
 
     let sample_warehouse = Warehouse::from(sample_input.as_str());
 
     let (_, sample_available) = sample_warehouse.clone().find_grabbable();
-    let (_, sample_removed) = sample_warehouse.remove_all();
+    let (_, sample_removed) = sample_warehouse.clone().remove_all();
 
     println!("Available (Sample): {}", sample_available);
     println!("Removed (Sample): {}", sample_removed);
 
+    let rules = [
+        ("von Neumann < 2", Rule::new(Neighbourhood::VonNeumann, 2)),
+        ("Moore < 4", Rule::new(Neighbourhood::Moore, 4)),
+        ("Radius 2 < 12", Rule::new(Neighbourhood::Radius(2), 12)),
+        (
+            "Diagonals < 2",
+            Rule::new(
+                Neighbourhood::Custom(vec![(-1, -1), (-1, 1), (1, 1), (1, -1)]),
+                2,
+            ),
+        ),
+    ];
+
+    for (name, rule) in rules.iter() {
+        let (_, available) = sample_warehouse.clone().find_grabbable_with(rule);
+        let (_, removed) = sample_warehouse.clone().remove_all_with(rule);
+
+        println!(
+            "{} (Sample): available {}, removed {}",
+            name, available, removed
+        );
+    }
+
+    for edge in [Edge::Open, Edge::Walls, Edge::Torus] {
+        let (_, available) = sample_warehouse.clone().with_edge(edge).find_grabbable();
+        let (_, removed) = sample_warehouse.clone().with_edge(edge).remove_all();
+
+        println!(
+            "{:?} edges (Sample): available {}, removed {}",
+            edge, available, removed
+        );
+    }
+
     let input = fs::read_to_string("input.txt").unwrap();
 
     let warehouse = Warehouse::from(input.as_str());
 
     let (_, available) = warehouse.clone().find_grabbable();
-    let (_, removed) = warehouse.remove_all();
+    let (_, removed) = warehouse.clone().remove_all();
 
     println!("Available (Input): {}", available);
     println!("Removed (Input): {}", removed);
+
+    #[cfg(feature = "parallel")]
+    {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let (_, removed) = warehouse
+            .clone()
+            .remove_all_parallel(&Rule::default(), threads);
+
+        println!("Removed (Input, {} threads): {}", threads, removed);
+    }
+
+    // cargo run -- <output dir>
+    if let Some(dir) = std::env::args().nth(1) {
+        let dir = Path::new(&dir);
+        let history = warehouse.history(&Rule::default());
+
+        fs::create_dir_all(dir).unwrap();
+
+        let mut ascii = io::BufWriter::new(fs::File::create(dir.join("frames.txt")).unwrap());
+        history.write_ascii(&mut ascii).unwrap();
+
+        history.write_ppm_series(dir, 4).unwrap();
+
+        println!("Wrote {} rounds to {}", history.rounds.len(), dir.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    const SAMPLE: &str = "\
+..@@.@@@@.
+@@@.@@.@.@
+@@@@@.@.@@
+@.@@@@..@.
+@@.@@@@.@@
+.@@@@@@@.@
+.@.@.@.@@@
+@.@@@.@@@@
+.@@@@@@@@.
+@.@.@@@.@.
+";
+
+    // Deterministic pseudo-random warehouse, roughly `fill` percent rolls
+    fn random_warehouse(rows: usize, cols: usize, fill: u64, seed: u64) -> Warehouse {
+        let mut state = seed;
+
+        Warehouse {
+            grid: (0..rows)
+                .map(|_| {
+                    (0..cols)
+                        .map(|_| {
+                            state = state
+                                .wrapping_mul(6364136223846793005)
+                                .wrapping_add(1442695040888963407);
+                            if (state >> 33) % 100 < fill {
+                                Tile::Roll
+                            } else {
+                                Tile::Empty
+                            }
+                        })
+                        .collect()
+                })
+                .collect(),
+            edge: Edge::Open,
+        }
+    }
+
+    #[test]
+    fn sample() {
+        let warehouse = Warehouse::from(SAMPLE);
+
+        let (fast, fast_removed) = warehouse.clone().remove_all();
+        let (slow, slow_removed) = warehouse.remove_all_rescan(&Rule::default());
+
+        assert_eq!(fast_removed, slow_removed);
+        assert_eq!(fast.grid, slow.grid);
+    }
+
+    #[test]
+    fn worklist_matches_rescan() {
+        for seed in 0..20 {
+            let warehouse = random_warehouse(30, 40, 70, seed);
+
+            let (fast, fast_removed) = warehouse.clone().remove_all();
+            let (slow, slow_removed) = warehouse.remove_all_rescan(&Rule::default());
+
+            assert_eq!(fast_removed, slow_removed, "seed {}", seed);
+            assert_eq!(fast.grid, slow.grid, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn history_matches_rescan_rounds() {
+        let warehouse = random_warehouse(20, 20, 70, 7);
+        let rule = Rule::default();
+
+        let history = warehouse.history(&rule);
+        let frames = history.frames();
+
+        assert_eq!(frames.len(), history.rounds.len() + 1);
+
+        let mut state = warehouse;
+        for (round, frame) in history.rounds.iter().zip(frames.iter()) {
+            let (marked, removeable) = state.find_grabbable_with(&rule);
+
+            assert_eq!(removeable, round.len());
+            assert_eq!(marked.grid, frame.grid);
+
+            state = marked.remove_grabbable();
+        }
+
+        assert_eq!(state.clone().find_grabbable_with(&rule).1, 0);
+        assert_eq!(state.grid, frames.last().unwrap().grid);
+    }
+
+    #[test]
+    fn ascii_and_ppm_export() {
+        let warehouse = Warehouse::from("@@@\n@@@\n.@.\n");
+        let history = warehouse.history(&Rule::default());
+
+        let mut ascii = Vec::new();
+        history.write_ascii(&mut ascii).unwrap();
+        let ascii = String::from_utf8(ascii).unwrap();
+
+        assert!(ascii.starts_with("Round 1 (3 removed)\nx@x\n@@@\n.x.\n"));
+        assert!(ascii.contains("Final\n...\n...\n...\n"));
+
+        let mut ppm = Vec::new();
+        write_ppm(&warehouse, 2, &mut ppm).unwrap();
+
+        let header = b"P6\n6 6\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + 6 * 6 * 3);
+    }
+
+    #[test]
+    fn edge_modes() {
+        let full = Warehouse::from("@@@\n@@@\n@@@\n");
+
+        assert_eq!(full.clone().find_grabbable().1, 4);
+        assert_eq!(full.clone().with_edge(Edge::Walls).remove_all().1, 0);
+        assert_eq!(full.clone().with_edge(Edge::Torus).remove_all().1, 0);
+
+        let walled = Warehouse::from("@..\n...\n...\n").with_edge(Edge::Walls);
+        assert_eq!(walled.get(-1, 0), Some(Tile::Wall));
+        assert_eq!(walled.count_adjacent(0, 0, &Rule::default()), 5);
+
+        let torus = Warehouse::from("@..\n...\n..@\n").with_edge(Edge::Torus);
+        assert_eq!(torus.get(-1, -1), Some(Tile::Roll));
+        assert_eq!(torus.count_adjacent(0, 0, &Rule::default()), 1);
+    }
+
+    #[test]
+    fn edge_modes_match_rescan() {
+        for edge in [Edge::Walls, Edge::Torus] {
+            for seed in 0..10 {
+                let warehouse = random_warehouse(20, 30, 60, seed).with_edge(edge);
+
+                let (fast, fast_removed) = warehouse.clone().remove_all();
+                let (slow, slow_removed) = warehouse.remove_all_rescan(&Rule::default());
+
+                assert_eq!(fast_removed, slow_removed, "{:?} seed {}", edge, seed);
+                assert_eq!(fast.grid, slow.grid, "{:?} seed {}", edge, seed);
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_rounds_match_sequential() {
+        for (seed, edge) in [(0, Edge::Open), (1, Edge::Walls), (2, Edge::Torus)] {
+            for threads in [1, 3, 8] {
+                let rule = Rule::default();
+                let mut sequential = random_warehouse(37, 23, 70, seed).with_edge(edge);
+                let mut current = sequential.clone();
+                let mut next = current.clone();
+
+                loop {
+                    let (marked, expected) = sequential.find_grabbable_with(&rule);
+                    sequential = marked.remove_grabbable();
+
+                    let removed = current.step_parallel(&mut next, &rule, threads);
+                    std::mem::swap(&mut current, &mut next);
+
+                    assert_eq!(removed, expected, "{:?} {} threads", edge, threads);
+                    assert_eq!(
+                        current.grid, sequential.grid,
+                        "{:?} {} threads",
+                        edge, threads
+                    );
+
+                    if removed == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_total_matches_worklist() {
+        let warehouse = random_warehouse(200, 150, 70, 11);
+
+        let (fast, fast_removed) = warehouse.clone().remove_all();
+        let (parallel, parallel_removed) = warehouse.remove_all_parallel(&Rule::default(), 4);
+
+        assert_eq!(fast_removed, parallel_removed);
+        assert_eq!(fast.grid, parallel.grid);
+    }
+
+    #[test]
+    fn neighbourhood_offsets() {
+        assert_eq!(Neighbourhood::VonNeumann.offsets().len(), 4);
+        assert_eq!(Neighbourhood::Moore.offsets().len(), 8);
+        assert_eq!(Neighbourhood::Radius(1).offsets().len(), 8);
+        assert_eq!(Neighbourhood::Radius(2).offsets().len(), 24);
+    }
+
+    #[test]
+    fn configured_rules_match_rescan() {
+        let rules = [
+            Rule::new(Neighbourhood::VonNeumann, 2),
+            Rule::new(Neighbourhood::Radius(2), 10),
+            // Asymmetric: only looks right and down
+            Rule::new(Neighbourhood::Custom(vec![(0, 1), (1, 0), (1, 1)]), 2),
+        ];
+
+        for rule in rules.iter() {
+            for seed in 0..10 {
+                let warehouse = random_warehouse(25, 25, 65, seed);
+
+                let (fast, fast_removed) = warehouse.clone().remove_all_with(rule);
+                let (slow, slow_removed) = warehouse.remove_all_rescan(rule);
+
+                assert_eq!(fast_removed, slow_removed, "{:?} seed {}", rule, seed);
+                assert_eq!(fast.grid, slow.grid, "{:?} seed {}", rule, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn threshold_zero_removes_nothing() {
+        let warehouse = random_warehouse(10, 10, 50, 3);
+
+        let rule = Rule::new(Neighbourhood::Moore, 0);
+
+        assert_eq!(warehouse.remove_all_with(&rule).1, 0);
+    }
+
+    // cargo test --release -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_remove_all_2000() {
+        let warehouse = random_warehouse(2000, 2000, 70, 1);
+
+        let start = Instant::now();
+        let (_, removed) = warehouse.remove_all();
+
+        println!("2000x2000: removed {} in {:?}", removed, start.elapsed());
+    }
 }
@@ -1,22 +1,66 @@
 use std::cmp::{Ordering, max, min};
-use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs;
+use std::ops::{BitAnd, BitOr, Sub};
 
 type Range = (usize, usize);
 type Ranges = Vec<Range>;
 type Ids = Vec<usize>;
 
+/// Upper end of a range with no upper bound. IDs are `usize`, so an explicit upper bound of
+/// `usize::MAX` covers exactly the same IDs and is treated as unbounded too.
+const UNBOUNDED: usize = usize::MAX;
+
+/// Parse a range as inclusive `a-b`, exclusive `a..b`, open ended `a-` or `-b`, or a single ID.
+/// Returns `None` for an exclusive range that contains nothing.
+fn parse_range(line: &str) -> Option<Range> {
+    let parse = |s: &str| s.parse::<usize>().unwrap();
+
+    if let Some((l, r)) = line.split_once("..") {
+        let (l, r) = (parse(l), parse(r));
+        return if l < r { Some((l, r - 1)) } else { None };
+    }
+
+    match line.split_once("-") {
+        Some(("", "")) => panic!("Range {:?} has no bounds", line),
+        Some(("", r)) => Some((0, parse(r))),
+        Some((l, "")) => Some((parse(l), UNBOUNDED)),
+        Some((l, r)) => Some((parse(l), parse(r))),
+        None => Some((parse(line), parse(line))),
+    }
+}
+
 fn parse_pairs(input: &str) -> Ranges {
     input
         .split("\n")
         .filter(|l| !l.is_empty())
-        .map(|l| {
-            let (l, r) = l.split_once("-").unwrap();
-            (l.parse().unwrap(), r.parse().unwrap())
-        })
+        .filter_map(parse_range)
         .collect()
 }
 
+/// Number of IDs in a range, or `None` if it has no upper bound
+fn range_len(range: &Range) -> Option<usize> {
+    match range {
+        (_, UNBOUNDED) => None,
+        (l, h) => Some(h - l + 1),
+    }
+}
+
+/// Bounds only, as `a-b` or `a-`
+fn format_bounds(range: &Range) -> String {
+    match range.1 {
+        UNBOUNDED => format!("{}-", range.0),
+        end => format!("{}-{}", range.0, end),
+    }
+}
+
+fn format_range(range: &Range) -> String {
+    match range_len(range) {
+        Some(n) => format!("{} ({} IDs)", format_bounds(range), n),
+        None => format!("{} (unbounded)", format_bounds(range)),
+    }
+}
+
 fn parse_ids(input: &str) -> Ids {
     input
         .split("\n")
@@ -43,102 +87,642 @@ fn num_compare(id: usize, range: &Range) -> Ordering {
     }
 }
 
-/// Convert set of overlapping ranges into non-overlapping ranges
-fn collapse_ranges(mut ranges: Ranges) -> Ranges {
-    // Sort range by minimum value ascending
-    ranges.sort_by_key(|k| k.0);
+/// Set of IDs stored as sorted, disjoint and non-adjacent inclusive ranges
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct IntervalSet(Ranges);
+
+impl IntervalSet {
+    fn ranges(&self) -> &[Range] {
+        &self.0
+    }
+
+    /// Add a range, merging with any range it overlaps or touches
+    fn insert(&mut self, range: Range) {
+        let (lo, hi) = range;
+
+        // Ranges `i..j` overlap or touch the new range
+        let i = self.0.partition_point(|r| r.1.saturating_add(1) < lo);
+        let j = self.0.partition_point(|r| r.0 <= hi.saturating_add(1));
+
+        let merged = if i < j {
+            (min(lo, self.0[i].0), max(hi, self.0[j - 1].1))
+        } else {
+            range
+        };
+
+        self.0.splice(i..j, [merged]);
+    }
+
+    /// Remove a range, splitting any range it falls inside of
+    fn remove(&mut self, range: Range) {
+        let (lo, hi) = range;
+
+        let i = self.0.partition_point(|r| r.1 < lo);
+        let j = self.0.partition_point(|r| r.0 <= hi);
 
-    fn fold_ranges(mut acc: Ranges, e: Range) -> Ranges {
+        if i >= j {
+            return;
+        }
+
+        let mut remainder = Vec::with_capacity(2);
+
+        if self.0[i].0 < lo {
+            remainder.push((self.0[i].0, lo - 1));
+        }
+        if self.0[j - 1].1 > hi {
+            remainder.push((hi + 1, self.0[j - 1].1));
+        }
+
+        self.0.splice(i..j, remainder);
+    }
+
+    /// Range containing `id`, if any
+    fn find(&self, id: usize) -> Option<Range> {
         // `num_compare` reversed because probe function tests a given range in relation to a
         // number. This is the opposite behaviour from normal.
-        let index = acc.binary_search_by(|probe| num_compare(e.0, probe).reverse());
+        self.0
+            .binary_search_by(|probe| num_compare(id, probe).reverse())
+            .ok()
+            .map(|i| self.0[i])
+    }
 
-        // Modify existing range or append a non-overlapping range
-        match index {
-            Ok(i) => {
-                let existing = acc[i];
-                acc[i] = (min(existing.0, e.0), max(existing.1, e.1));
-            }
-            Err(i) => {
-                acc.insert(i, e);
-            }
+    fn contains(&self, id: usize) -> bool {
+        self.find(id).is_some()
+    }
+
+    /// Range containing each ID, in the order given. Sweeps the IDs in sorted order alongside
+    /// the ranges, so a batch costs one sort plus a single pass.
+    fn find_all(&self, ids: &[usize]) -> Vec<(usize, Option<Range>)> {
+        let mut order = (0..ids.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| ids[i]);
+
+        let mut found = vec![None; ids.len()];
+        let mut ranges = self.0.iter().peekable();
+
+        for i in order {
+            while ranges.next_if(|r| r.1 < ids[i]).is_some() {}
+
+            found[i] = ranges.peek().filter(|r| r.0 <= ids[i]).copied().copied();
+        }
+
+        ids.iter().copied().zip(found).collect()
+    }
+
+    /// Every ID within `bounds` that is not in the set
+    fn complement(&self, bounds: Range) -> Self {
+        let mut out = Self(vec![bounds]);
+
+        for range in self.0.iter() {
+            out.remove(*range);
         }
 
-        acc
+        out
     }
 
-    ranges.into_iter().fold(Vec::new(), fold_ranges)
+    /// Number of IDs in the set, or `None` if it has no upper bound
+    fn len(&self) -> Option<usize> {
+        self.0.iter().map(range_len).sum()
+    }
 }
 
-/// Part 1. Check if a given ID is any range.
-fn num_ids_inside_range(ranges: &Ranges, ids: Ids) -> usize {
-    let mut count = 0;
-
-    for id in ids {
-        for range in ranges.iter() {
-            if num_compare(id, range) == Ordering::Equal {
-                count += 1;
-                break;
+impl From<Ranges> for IntervalSet {
+    fn from(mut ranges: Ranges) -> Self {
+        // Sorted input always merges into the last range, keeping inserts O(log n)
+        ranges.sort_by_key(|k| k.0);
+
+        let mut set = Self::default();
+
+        for range in ranges {
+            set.insert(range);
+        }
+
+        set
+    }
+}
+
+impl BitOr for &IntervalSet {
+    type Output = IntervalSet;
+
+    fn bitor(self, rhs: Self) -> IntervalSet {
+        let mut out = self.clone();
+
+        for range in rhs.0.iter() {
+            out.insert(*range);
+        }
+
+        out
+    }
+}
+
+impl BitAnd for &IntervalSet {
+    type Output = IntervalSet;
+
+    fn bitand(self, rhs: Self) -> IntervalSet {
+        let mut out = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.0.len() && j < rhs.0.len() {
+            let (a, b) = (self.0[i], rhs.0[j]);
+
+            let lo = max(a.0, b.0);
+            let hi = min(a.1, b.1);
+
+            if lo <= hi {
+                out.push((lo, hi));
             }
+
+            if a.1 < b.1 { i += 1 } else { j += 1 }
         }
+
+        IntervalSet(out)
     }
+}
+
+impl Sub for &IntervalSet {
+    type Output = IntervalSet;
 
-    count
+    fn sub(self, rhs: Self) -> IntervalSet {
+        let mut out = self.clone();
+
+        for range in rhs.0.iter() {
+            out.remove(*range);
+        }
+
+        out
+    }
 }
 
-/// Part 2. Directly calculate number of IDs. Requires no ranges have overlapping boundaries.
-fn total_num_unique_ids(ranges: &Ranges) -> usize {
-    assert!(ranges.iter().flat_map(|(l, r)| [*l, *r]).is_sorted());
-    ranges.iter().map(|(l, h)| h - l + 1).sum()
+/// Original input ranges arranged as a balanced binary tree over an array sorted by start.
+/// The node for `lo..hi` sits at the midpoint, and `max_end` holds the highest end point
+/// anywhere in that node's subtree so whole branches can be skipped.
+struct IntervalTree {
+    nodes: Vec<(Range, usize)>,
+    max_end: Vec<usize>,
 }
 
-/// Debugging. Prints ordered rank of number for easier comparison.
-fn print_range(ranges: &Ranges) -> String {
-    let lookup = {
-        let mut v = ranges
-            .iter()
-            .flat_map(|(l, h)| [*l, *h])
-            .collect::<Vec<_>>();
-        v.sort();
-        v.into_iter()
-            .enumerate()
-            .map(|(i, v)| (v, i))
-            .collect::<HashMap<usize, usize>>()
+impl IntervalTree {
+    fn new(ranges: &Ranges) -> Self {
+        let mut nodes = ranges.iter().copied().zip(0..).collect::<Vec<_>>();
+        nodes.sort();
+
+        let mut tree = Self {
+            max_end: vec![0; nodes.len()],
+            nodes,
+        };
+        tree.build(0, tree.nodes.len());
+
+        tree
+    }
+
+    fn build(&mut self, lo: usize, hi: usize) -> usize {
+        if lo >= hi {
+            return 0;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+        let left = self.build(lo, mid);
+        let right = self.build(mid + 1, hi);
+
+        self.max_end[mid] = max(self.nodes[mid].0.1, max(left, right));
+        self.max_end[mid]
+    }
+
+    /// Indices of the input ranges containing `id`
+    fn stab(&self, id: usize) -> Vec<usize> {
+        self.overlapping((id, id))
+    }
+
+    /// Indices of the input ranges sharing at least one ID with `range`, in input line order
+    fn overlapping(&self, range: Range) -> Vec<usize> {
+        let mut out = Vec::new();
+        self.collect(0, self.nodes.len(), range, &mut out);
+        out.sort();
+        out
+    }
+
+    fn collect(&self, lo: usize, hi: usize, range: Range, out: &mut Vec<usize>) {
+        if lo >= hi {
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+
+        // Nothing in this subtree reaches the query
+        if self.max_end[mid] < range.0 {
+            return;
+        }
+
+        self.collect(lo, mid, range, out);
+
+        // Everything from here on starts after the query
+        let (node, index) = self.nodes[mid];
+        if node.0 > range.1 {
+            return;
+        }
+
+        if node.1 >= range.0 {
+            out.push(index);
+        }
+
+        self.collect(mid + 1, hi, range, out);
+    }
+}
+
+/// Part 1. Check if a given ID is any range.
+fn num_ids_inside_range(ranges: &IntervalSet, ids: Ids) -> usize {
+    ids.into_iter().filter(|&id| ranges.contains(id)).count()
+}
+
+/// Part 2. Directly calculate number of IDs. `None` when a range has no upper bound.
+fn total_num_unique_ids(ranges: &IntervalSet) -> Option<usize> {
+    ranges.len()
+}
+
+/// Merged ranges, the gaps between them, and which IDs landed in those gaps. With `bar_width`
+/// also draws the span of all ranges as a scaled timeline.
+fn coverage_report(ranges: &IntervalSet, ids: &[usize], bar_width: Option<usize>) -> String {
+    let (Some(first), Some(last)) = (ranges.ranges().first(), ranges.ranges().last()) else {
+        return String::from("No ranges");
     };
 
-    let mut out = Vec::new();
+    let span = (first.0, last.1);
+    let gaps = ranges.complement(span);
 
-    for (l, h) in ranges {
-        out.push(format!(
-            "{}({})-{}({})",
-            lookup.get(l).unwrap(),
-            l,
-            lookup.get(h).unwrap(),
-            h
-        ));
+    let mut out = vec![format!("Merged Ranges ({}):", ranges.ranges().len())];
+    out.extend(
+        ranges
+            .ranges()
+            .iter()
+            .map(|r| format!("  {}", format_range(r))),
+    );
+
+    out.push(format!("Gaps ({}):", gaps.ranges().len()));
+    out.extend(
+        gaps.ranges()
+            .iter()
+            .map(|r| format!("  {}", format_range(r))),
+    );
+
+    out.push(match ranges.len() {
+        Some(n) => format!("Total Covered: {}", n),
+        None => String::from("Total Covered: unbounded"),
+    });
+
+    let mut in_gaps = ids
+        .iter()
+        .copied()
+        .filter(|&id| gaps.contains(id))
+        .collect::<Vec<_>>();
+    in_gaps.sort();
+    out.push(format!("IDs In Gaps ({}): {:?}", in_gaps.len(), in_gaps));
+
+    let outside = ids.iter().filter(|&&id| id < span.0 || id > span.1).count();
+    out.push(format!("IDs Outside {}: {}", format_bounds(&span), outside));
+
+    if let Some(width) = bar_width {
+        // Draw an unbounded tail up to whichever is further out: its start or the last ID
+        let end = match span.1 {
+            UNBOUNDED => max(last.0, ids.iter().copied().max().unwrap_or(0)),
+            end => end,
+        };
+
+        out.push(timeline(ranges, (span.0, end), width));
     }
 
     out.join("\n")
 }
 
+/// One character per bucket of `bounds`: `#` fully covered, `+` partly covered, `.` uncovered
+fn timeline(ranges: &IntervalSet, bounds: Range, width: usize) -> String {
+    let size = bounds.1 - bounds.0 + 1;
+    let width = width.clamp(1, size);
+
+    let bar = (0..width)
+        .map(|k| {
+            let lo = bounds.0 + k * size / width;
+            let hi = bounds.0 + (k + 1) * size / width - 1;
+
+            let bucket = IntervalSet::from(vec![(lo, hi)]);
+
+            match (&bucket & ranges).len().unwrap() {
+                0 => '.',
+                n if n == hi - lo + 1 => '#',
+                _ => '+',
+            }
+        })
+        .collect::<String>();
+
+    let end = bounds.1.to_string();
+    format!(
+        "{:<w$}{}\n|{}|",
+        bounds.0,
+        end,
+        bar,
+        w = (width + 2).saturating_sub(end.len())
+    )
+}
+
+enum Command {
+    Solve,
+    Report { bar_width: Option<usize> },
+}
+
+impl From<&[String]> for Command {
+    fn from(args: &[String]) -> Self {
+        match args.first().map(|a| a.as_str()) {
+            None => Self::Solve,
+            Some("report") => Self::Report {
+                bar_width: args.iter().position(|a| a == "--bar").map(|i| {
+                    args.get(i + 1)
+                        .and_then(|w| w.parse().ok())
+                        .expect("--bar takes a width")
+                }),
+            },
+            Some(other) => panic!("Unknown command {:?}", other),
+        }
+    }
+}
+
 fn main() {
-    fn run(input: &str) {
+    fn run(input: &str, command: &Command) {
         let (ranges, ids) = parse_input(input);
         println!("Original Ranges Count: {}", ranges.len());
 
-        let new_ranges = collapse_ranges(ranges);
-        println!("Non-Overlapping Ranges Count: {}", new_ranges.len());
-        println!("\n{}\n", print_range(&new_ranges));
+        let tree = IntervalTree::new(&ranges);
+
+        let multiply_covered = ids.iter().filter(|&&id| tree.stab(id).len() > 1).count();
+        println!("IDs In Multiple Ranges: {}", multiply_covered);
+
+        let overlapping_pairs = ranges
+            .iter()
+            .map(|&r| tree.overlapping(r).len() - 1)
+            .sum::<usize>()
+            / 2;
+        println!("Overlapping Range Pairs: {}", overlapping_pairs);
+
+        let new_ranges = IntervalSet::from(ranges);
+        println!(
+            "Non-Overlapping Ranges Count: {}",
+            new_ranges.ranges().len()
+        );
+
+        if let Command::Report { bar_width } = command {
+            println!("\n{}\n", coverage_report(&new_ranges, &ids, *bar_width));
+        }
+
+        let matched = new_ranges
+            .find_all(&ids)
+            .into_iter()
+            .filter_map(|(_, range)| range)
+            .collect::<HashSet<_>>();
+        println!("Ranges Matching An ID: {}", matched.len());
 
         let p1 = num_ids_inside_range(&new_ranges, ids);
         println!("P1: {}", p1);
 
-        let p2 = total_num_unique_ids(&new_ranges);
-        println!("P2: {}\n", p2);
+        match total_num_unique_ids(&new_ranges) {
+            Some(p2) => println!("P2: {}\n", p2),
+            None => println!("P2: unbounded\n"),
+        }
     }
+    // cargo run -- report [--bar <width>]
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let command = Command::from(args.as_slice());
+
     let s_input = fs::read_to_string("sample_input.txt").unwrap();
-    run(s_input.as_str());
+    run(s_input.as_str(), &command);
 
     let input = fs::read_to_string("input.txt").unwrap();
-    run(input.as_str());
+    run(input.as_str(), &command);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_overlapping_and_adjacent() {
+        let set = IntervalSet::from(vec![(10, 14), (3, 5), (16, 20), (12, 18), (6, 8), (30, 30)]);
+
+        assert_eq!(set.ranges(), &[(3, 8), (10, 20), (30, 30)]);
+        assert_eq!(set.len(), Some(6 + 11 + 1));
+    }
+
+    #[test]
+    fn insert_bridges_ranges() {
+        let mut set = IntervalSet::from(vec![(1, 2), (5, 6), (9, 10)]);
+
+        set.insert((3, 8));
+        assert_eq!(set.ranges(), &[(1, 10)]);
+
+        set.insert((0, 0));
+        set.insert((20, 25));
+        set.insert((12, 12));
+        assert_eq!(set.ranges(), &[(0, 10), (12, 12), (20, 25)]);
+    }
+
+    #[test]
+    fn remove_splits_ranges() {
+        let mut set = IntervalSet::from(vec![(0, 10), (20, 30)]);
+
+        set.remove((3, 5));
+        assert_eq!(set.ranges(), &[(0, 2), (6, 10), (20, 30)]);
+
+        set.remove((8, 25));
+        assert_eq!(set.ranges(), &[(0, 2), (6, 7), (26, 30)]);
+
+        set.remove((0, 30));
+        assert_eq!(set.ranges(), &[]);
+    }
+
+    #[test]
+    fn contains() {
+        let set = IntervalSet::from(vec![(3, 5), (10, 14), (16, 20), (12, 18)]);
+
+        let fresh = (0..25).filter(|&id| set.contains(id)).collect::<Vec<_>>();
+
+        assert_eq!(
+            fresh,
+            vec![3, 4, 5, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20]
+        );
+    }
+
+    #[test]
+    fn find_all_matches_find() {
+        let set = IntervalSet::from(vec![(3, 5), (10, 14), (16, 20), (12, 18), (40, 40)]);
+        let ids = vec![41, 17, 0, 5, 40, 8, 3, 21, 17, 9];
+
+        let found = set.find_all(&ids);
+
+        assert_eq!(
+            found,
+            ids.iter().map(|&id| (id, set.find(id))).collect::<Vec<_>>()
+        );
+        assert_eq!(found[1], (17, Some((10, 20))));
+        assert_eq!(found[4], (40, Some((40, 40))));
+        assert_eq!(found[5], (8, None));
+    }
+
+    #[test]
+    fn interval_tree_matches_scan() {
+        let ranges = vec![
+            (20, 30),
+            (3, 5),
+            (25, 25),
+            (10, 14),
+            (40, 52),
+            (0, 2),
+            (16, 20),
+            (12, 18),
+            (3, 5),
+            (31, 39),
+            (45, 47),
+        ];
+        let tree = IntervalTree::new(&ranges);
+
+        let overlaps = |q: Range| {
+            (0..ranges.len())
+                .filter(|&i| ranges[i].0 <= q.1 && q.0 <= ranges[i].1)
+                .collect::<Vec<_>>()
+        };
+
+        for id in 0..60 {
+            assert_eq!(tree.stab(id), overlaps((id, id)), "id {}", id);
+        }
+
+        for lo in 0..60 {
+            for hi in lo..60 {
+                assert_eq!(tree.overlapping((lo, hi)), overlaps((lo, hi)));
+            }
+        }
+    }
+
+    #[test]
+    fn interval_tree_sample() {
+        let (ranges, _) = parse_input("3-5\n10-14\n16-20\n12-18\n\n1\n");
+        let tree = IntervalTree::new(&ranges);
+
+        assert_eq!(tree.stab(17), vec![2, 3]);
+        assert_eq!(tree.stab(13), vec![1, 3]);
+        assert_eq!(tree.stab(8), Vec::<usize>::new());
+        assert_eq!(tree.overlapping((5, 10)), vec![0, 1]);
+    }
+
+    #[test]
+    fn report() {
+        let (ranges, ids) = parse_input("3-5\n10-14\n16-20\n12-18\n\n1\n5\n8\n11\n17\n32\n");
+        let set = IntervalSet::from(ranges);
+
+        let report = coverage_report(&set, &ids, Some(18));
+
+        assert_eq!(
+            report,
+            "\
+Merged Ranges (2):
+  3-5 (3 IDs)
+  10-20 (11 IDs)
+Gaps (1):
+  6-9 (4 IDs)
+Total Covered: 14
+IDs In Gaps (1): [8]
+IDs Outside 3-20: 2
+3                 20
+|###....###########|"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "has no bounds")]
+    fn parse_range_without_bounds() {
+        parse_range("-");
+    }
+
+    #[test]
+    fn report_command() {
+        let args = ["report", "--bar", "30"].map(String::from);
+
+        assert!(matches!(
+            Command::from(&args[..]),
+            Command::Report {
+                bar_width: Some(30)
+            }
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "--bar takes a width")]
+    fn report_command_missing_width() {
+        let args = ["report", "--bar"].map(String::from);
+        let _ = Command::from(&args[..]);
+    }
+
+    #[test]
+    fn timeline_partial_buckets() {
+        let set = IntervalSet::from(vec![(0, 9), (15, 19)]);
+
+        assert!(timeline(&set, (0, 19), 4).ends_with("|##.#|"));
+        assert!(timeline(&set, (0, 19), 2).ends_with("|#+|"));
+    }
+
+    #[test]
+    fn range_syntax() {
+        assert_eq!(parse_range("3-5"), Some((3, 5)));
+        assert_eq!(parse_range("3..5"), Some((3, 4)));
+        assert_eq!(parse_range("3..3"), None);
+        assert_eq!(parse_range("7"), Some((7, 7)));
+        assert_eq!(parse_range("-5"), Some((0, 5)));
+        assert_eq!(parse_range("10-"), Some((10, UNBOUNDED)));
+        assert_eq!(
+            parse_range("10-18446744073709551615"),
+            Some((10, UNBOUNDED))
+        );
+    }
+
+    #[test]
+    fn mixed_syntax_counts() {
+        let (ranges, ids) = parse_input("-2\n5..8\n8\n20-22\n4..4\n\n0\n4\n8\n9\n21\n");
+        let set = IntervalSet::from(ranges);
+
+        assert_eq!(set.ranges(), &[(0, 2), (5, 8), (20, 22)]);
+        assert_eq!(total_num_unique_ids(&set), Some(10));
+        assert_eq!(num_ids_inside_range(&set, ids), 3);
+    }
+
+    #[test]
+    fn unbounded_ranges() {
+        let (ranges, ids) = parse_input("3-5\n100-\n90..101\n\n2\n4\n95\n1000000\n");
+        let set = IntervalSet::from(ranges);
+
+        assert_eq!(set.ranges(), &[(3, 5), (90, UNBOUNDED)]);
+        assert_eq!(total_num_unique_ids(&set), None);
+        assert_eq!(num_ids_inside_range(&set, ids.clone()), 3);
+        assert_eq!(set.complement((0, UNBOUNDED)).ranges(), &[(0, 2), (6, 89)]);
+
+        let report = coverage_report(&set, &ids, Some(10));
+        assert!(report.contains("  90- (unbounded)"));
+        assert!(report.contains("IDs Outside 3-: 1"));
+        assert!(report.contains("Total Covered: unbounded"));
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = IntervalSet::from(vec![(0, 10), (20, 30)]);
+        let b = IntervalSet::from(vec![(5, 22), (28, 40)]);
+
+        assert_eq!((&a | &b).ranges(), &[(0, 40)]);
+        assert_eq!((&a & &b).ranges(), &[(5, 10), (20, 22), (28, 30)]);
+        assert_eq!((&a - &b).ranges(), &[(0, 4), (23, 27)]);
+        assert_eq!((&b - &a).ranges(), &[(11, 19), (31, 40)]);
+        assert_eq!(a.complement((0, 50)).ranges(), &[(11, 19), (31, 50)]);
+        assert_eq!(a.complement((5, 25)).ranges(), &[(11, 19)]);
+    }
+
+    #[test]
+    fn sample() {
+        let (ranges, ids) = parse_input("3-5\n10-14\n16-20\n12-18\n\n1\n5\n8\n11\n17\n32\n");
+        let set = IntervalSet::from(ranges);
+
+        assert_eq!(num_ids_inside_range(&set, ids), 3);
+        assert_eq!(total_num_unique_ids(&set), Some(14));
+    }
 }
@@ -1,122 +1,748 @@
 use std::collections::HashMap;
 use std::fs;
 
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+
 // squids shouldnt do math
 
-#[derive(Debug)]
-enum Operation {
-    Multiply(Vec<isize>),
-    Add(Vec<isize>),
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CalcError {
+    Overflow,
+    DivideByZero,
+    NegativeExponent,
+    UnknownOperator(char),
+    /// Operator cell holding more than one symbol
+    InvalidOperator(String),
+    MissingOperator,
+    InvalidOperand(String),
+    NoValues,
 }
 
-impl From<Vec<&str>> for Operation {
-    fn from(values: Vec<&str>) -> Self {
-        let mut values_iter = values.into_iter().rev();
+type CalcResult = Result<isize, CalcError>;
+
+#[cfg(feature = "bigint")]
+type BigApply = fn(BigInt, BigInt) -> Result<BigInt, CalcError>;
+
+/// Binary operator folded across every value in a problem. Right associative operators fold
+/// from the last value, so `2 ^ 3 ^ 2` is `2 ^ 9`. A problem only ever has one operator, so
+/// associativity is all that matters and there is no precedence between operators.
+#[derive(Clone, Copy)]
+struct Operator {
+    apply: fn(isize, isize) -> CalcResult,
+    right_associative: bool,
+    /// Arbitrary precision fallback for when `apply` overflows
+    #[cfg(feature = "bigint")]
+    apply_big: Option<BigApply>,
+}
 
-        fn collect_values<'a>(vs: impl Iterator<Item = &'a str>) -> Vec<isize> {
-            vs.map(|v| v.parse().unwrap()).collect()
+impl Operator {
+    fn left(apply: fn(isize, isize) -> CalcResult) -> Self {
+        Self {
+            apply,
+            right_associative: false,
+            #[cfg(feature = "bigint")]
+            apply_big: None,
         }
+    }
 
-        match values_iter.next() {
-            Some("*") => Self::Multiply(collect_values(values_iter)),
-            Some("+") => Self::Add(collect_values(values_iter)),
-            s => panic!("Unexpected operation {:?}", s),
+    fn right(apply: fn(isize, isize) -> CalcResult) -> Self {
+        Self {
+            apply,
+            right_associative: true,
+            #[cfg(feature = "bigint")]
+            apply_big: None,
         }
     }
-}
 
-impl Operation {
-    fn calculate(&self) -> isize {
-        match self {
-            Self::Multiply(v) => v.iter().map(|v| *v).reduce(|acc, e| acc * e).unwrap(),
-            Self::Add(v) => v.iter().sum(),
+    #[cfg(feature = "bigint")]
+    fn with_big(self, apply_big: BigApply) -> Self {
+        Self {
+            apply_big: Some(apply_big),
+            ..self
         }
     }
 }
 
-fn operation_with_rows(input: &str) -> isize {
-    let sets = input
-        .split("\n")
-        .filter(|l| !l.is_empty())
-        .map(|l| l.split(" ").filter(|ll| !ll.is_empty()).enumerate())
-        .fold(HashMap::<usize, Vec<&str>>::new(), |mut acc, e| {
-            e.into_iter().for_each(|(k, v)| {
-                let entry = acc.entry(k).or_insert(Vec::new());
-                entry.push(v);
-            });
-            acc
-        });
+/// Fold `apply` across `values` from the left, or from the right for right associative operators
+fn fold_values<T>(
+    values: impl DoubleEndedIterator<Item = T>,
+    right_associative: bool,
+    apply: impl Fn(T, T) -> Result<T, CalcError>,
+) -> Result<T, CalcError> {
+    let values = values.map(Ok);
 
-    let operations = sets
-        .into_iter()
-        .map(|(_, v)| Operation::from(v))
-        .collect::<Vec<_>>();
+    let result = if right_associative {
+        values.rev().reduce(|acc, e| apply(e?, acc?))
+    } else {
+        values.reduce(|acc, e| apply(acc?, e?))
+    };
 
-    operations.iter().map(|v| v.calculate()).sum::<isize>()
+    result.unwrap_or(Err(CalcError::NoValues))
 }
 
-fn operation_with_cols(input: &str) -> isize {
-    let grid = input
-        .split("\n")
-        .filter(|l| !l.is_empty())
-        .map(|l| l.chars().rev().collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+/// Worksheet symbols and the operators they stand for
+struct Registry(HashMap<char, Operator>);
 
-    let mut operations = Vec::new();
-    let mut values = Vec::new();
-    let mut operation_sign = None;
+impl Registry {
+    fn register(&mut self, symbol: char, operator: Operator) -> &mut Self {
+        self.0.insert(symbol, operator);
+        self
+    }
 
-    for col in 0..grid[0].len() {
-        let mut all_spaces = true;
-        let mut number_value = 0;
+    fn get(&self, symbol: char) -> Result<&Operator, CalcError> {
+        self.0
+            .get(&symbol)
+            .ok_or(CalcError::UnknownOperator(symbol))
+    }
+}
 
-        for row in 0..grid.len() {
-            let val = grid[row][col];
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self(HashMap::new());
 
-            all_spaces &= val == ' ';
+        registry
+            .register(
+                '+',
+                Operator::left(|a, b| a.checked_add(b).ok_or(CalcError::Overflow)),
+            )
+            .register(
+                '-',
+                Operator::left(|a, b| a.checked_sub(b).ok_or(CalcError::Overflow)),
+            )
+            .register(
+                '*',
+                Operator::left(|a, b| a.checked_mul(b).ok_or(CalcError::Overflow)),
+            )
+            .register(
+                '/',
+                Operator::left(|a, b| match b {
+                    0 => Err(CalcError::DivideByZero),
+                    _ => a.checked_div(b).ok_or(CalcError::Overflow),
+                }),
+            )
+            .register('<', Operator::left(|a, b| Ok(a.min(b))))
+            .register('>', Operator::left(|a, b| Ok(a.max(b))))
+            .register(
+                '^',
+                Operator::right(|a, b| {
+                    let exponent = u32::try_from(b).map_err(|_| match b < 0 {
+                        true => CalcError::NegativeExponent,
+                        false => CalcError::Overflow,
+                    })?;
+                    a.checked_pow(exponent).ok_or(CalcError::Overflow)
+                }),
+            );
 
-            match val {
-                '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                    number_value =
-                        number_value * 10 + isize::try_from(val.to_digit(10).unwrap()).unwrap();
-                }
-                '+' => operation_sign = Some('+'),
-                '*' => operation_sign = Some('*'),
-                _ => {}
-            };
+        #[cfg(feature = "bigint")]
+        for (symbol, apply_big) in [
+            ('+', (|a, b| Ok(a + b)) as BigApply),
+            ('-', |a, b| Ok(a - b)),
+            ('*', |a, b| Ok(a * b)),
+            ('/', |a, b| match b == BigInt::ZERO {
+                true => Err(CalcError::DivideByZero),
+                false => Ok(a / b),
+            }),
+            ('<', |a, b| Ok(a.min(b))),
+            ('>', |a, b| Ok(a.max(b))),
+            ('^', |a, b| {
+                let exponent = u32::try_from(&b).map_err(|_| match b < BigInt::ZERO {
+                    true => CalcError::NegativeExponent,
+                    false => CalcError::Overflow,
+                })?;
+                Ok(a.pow(exponent))
+            }),
+        ] {
+            let operator = registry.0[&symbol].with_big(apply_big);
+            registry.register(symbol, operator);
         }
-        if number_value > 0 {
-            values.push(number_value);
+
+        registry
+    }
+}
+
+/// Parse a worksheet number, reporting overflow instead of wrapping
+fn parse_value(text: &str) -> CalcResult {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+
+    if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(CalcError::InvalidOperand(text.to_string()));
+    }
+
+    digits.bytes().try_fold(0isize, |acc, c| {
+        let digit = (c - b'0') as isize;
+        acc.checked_mul(10)
+            .and_then(|acc| match negative {
+                true => acc.checked_sub(digit),
+                false => acc.checked_add(digit),
+            })
+            .ok_or(CalcError::Overflow)
+    })
+}
+
+/// One problem as written on the worksheet. Operands are kept as text and only parsed when
+/// calculating, so a number too large for `isize` is an error rather than a panic.
+#[derive(Debug)]
+struct Operation {
+    symbol: String,
+    operands: Vec<String>,
+}
+
+impl Operation {
+    fn symbol(&self) -> Result<char, CalcError> {
+        let mut chars = self.symbol.chars();
+
+        match (chars.next(), chars.next()) {
+            (None, _) => Err(CalcError::MissingOperator),
+            (Some(c), None) => Ok(c),
+            _ => Err(CalcError::InvalidOperator(self.symbol.clone())),
         }
+    }
+
+    fn values(&self) -> Result<Vec<isize>, CalcError> {
+        self.operands.iter().map(|o| parse_value(o)).collect()
+    }
 
-        if all_spaces {
-            values = Vec::new();
-            operation_sign = None;
+    fn calculate(&self, registry: &Registry) -> CalcResult {
+        let operator = registry.get(self.symbol()?)?;
+
+        fold_values(
+            self.values()?.into_iter(),
+            operator.right_associative,
+            operator.apply,
+        )
+    }
+
+    #[cfg(feature = "bigint")]
+    fn calculate_big(&self, registry: &Registry) -> Result<BigInt, CalcError> {
+        let operator = registry.get(self.symbol()?)?;
+        let apply_big = operator.apply_big.ok_or(CalcError::Overflow)?;
+
+        let values = self
+            .operands
+            .iter()
+            .map(|o| {
+                o.parse::<BigInt>()
+                    .map_err(|_| CalcError::InvalidOperand(o.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        fold_values(values.into_iter(), operator.right_associative, apply_big)
+    }
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.operands.join(&format!(" {} ", self.symbol)))
+    }
+}
+
+fn total(operations: &[Operation], registry: &Registry) -> CalcResult {
+    operations.iter().try_fold(0isize, |acc, e| {
+        acc.checked_add(e.calculate(registry)?)
+            .ok_or(CalcError::Overflow)
+    })
+}
+
+#[cfg(feature = "bigint")]
+fn total_big(operations: &[Operation], registry: &Registry) -> Result<BigInt, CalcError> {
+    operations.iter().try_fold(BigInt::ZERO, |acc, e| {
+        // Problems that fit stay on the fast path
+        let value = match e.calculate(registry) {
+            Ok(v) => BigInt::from(v),
+            Err(CalcError::Overflow) => e.calculate_big(registry)?,
+            Err(err) => return Err(err),
+        };
+        Ok(acc + value)
+    })
+}
+
+#[derive(Debug, PartialEq)]
+enum Total {
+    Exact(isize),
+    #[cfg(feature = "bigint")]
+    Big(BigInt),
+}
+
+impl std::fmt::Display for Total {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exact(n) => write!(f, "{}", n),
+            #[cfg(feature = "bigint")]
+            Self::Big(n) => write!(f, "{}", n),
         }
+    }
+}
+
+/// Sum of every problem, switching to arbitrary precision if `isize` overflows and the
+/// `bigint` feature is enabled
+fn evaluate(operations: &[Operation], registry: &Registry) -> Result<Total, CalcError> {
+    match total(operations, registry) {
+        Ok(n) => Ok(Total::Exact(n)),
+        #[cfg(feature = "bigint")]
+        Err(CalcError::Overflow) => total_big(operations, registry).map(Total::Big),
+        Err(e) => Err(e),
+    }
+}
+
+/// One problem on the worksheet: the columns between two blank columns, cut out of every line
+#[derive(Debug)]
+struct Block {
+    operands: Vec<String>,
+    operator: String,
+}
+
+/// Worksheet split into problem blocks, read either as one number per row or as one number
+/// per column
+#[derive(Debug)]
+struct Worksheet(Vec<Block>);
+
+impl From<&str> for Worksheet {
+    fn from(value: &str) -> Self {
+        let lines = value
+            .split("\n")
+            .filter(|l| !l.is_empty())
+            .map(|l| l.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
 
-        match operation_sign {
-            Some('*') => {
-                operations.push(Operation::Multiply(values));
-                values = Vec::new()
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+        let at = |line: &Vec<char>, col: usize| line.get(col).copied().unwrap_or(' ');
+
+        let blank = (0..width)
+            .map(|col| lines.iter().all(|l| at(l, col) == ' '))
+            .collect::<Vec<_>>();
+
+        let mut blocks = Vec::new();
+        let mut col = 0;
+
+        while col < width {
+            if blank[col] {
+                col += 1;
+                continue;
             }
-            Some('+') => {
-                operations.push(Operation::Add(values));
-                values = Vec::new()
+
+            let start = col;
+            while col < width && !blank[col] {
+                col += 1;
             }
-            None => {}
-            _ => panic!("unexpected value {:?}", operation_sign),
-        };
+
+            let mut cut = lines
+                .iter()
+                .map(|l| (start..col).map(|c| at(l, c)).collect::<String>())
+                .collect::<Vec<_>>();
+
+            let operator = cut.pop().unwrap().trim().to_string();
+
+            blocks.push(Block {
+                operands: cut,
+                operator,
+            });
+        }
+
+        Self(blocks)
+    }
+}
+
+impl Worksheet {
+    /// Part 1. Each row of a block is one number.
+    fn rows(&self) -> Vec<Operation> {
+        self.0
+            .iter()
+            .map(|b| Operation {
+                symbol: b.operator.clone(),
+                operands: b
+                    .operands
+                    .iter()
+                    .map(|o| o.trim())
+                    .filter(|o| !o.is_empty())
+                    .map(String::from)
+                    .collect(),
+            })
+            .collect()
     }
 
-    operations.iter().map(|v| v.calculate()).sum::<isize>()
+    /// Part 2. Each column of a block is one number, read top to bottom, with columns taken
+    /// right to left.
+    fn cols(&self) -> Vec<Operation> {
+        self.0
+            .iter()
+            .map(|b| {
+                let operands = b.operands.iter().map(|o| o.as_bytes()).collect::<Vec<_>>();
+                let width = operands.first().map_or(0, |o| o.len());
+
+                let operands = (0..width)
+                    .rev()
+                    .map(|col| {
+                        operands
+                            .iter()
+                            .map(|o| o[col] as char)
+                            .filter(|c| c.is_ascii_digit())
+                            .collect::<String>()
+                    })
+                    .filter(|o| !o.is_empty())
+                    .collect();
+
+                Operation {
+                    symbol: b.operator.clone(),
+                    operands,
+                }
+            })
+            .collect()
+    }
+}
+
+/// One problem evaluated under both readings
+struct Breakdown {
+    rows: Operation,
+    rows_value: CalcResult,
+    cols: Operation,
+    cols_value: CalcResult,
+}
+
+impl Worksheet {
+    /// Every problem in worksheet order, left to right
+    fn breakdown(&self, registry: &Registry) -> Vec<Breakdown> {
+        self.rows()
+            .into_iter()
+            .zip(self.cols())
+            .map(|(rows, cols)| Breakdown {
+                rows_value: rows.calculate(registry),
+                cols_value: cols.calculate(registry),
+                rows,
+                cols,
+            })
+            .collect()
+    }
+}
+
+fn format_table(breakdown: &[Breakdown]) -> String {
+    let format_value = |v: &CalcResult| match v {
+        Ok(n) => n.to_string(),
+        Err(e) => format!("{:?}", e),
+    };
+
+    let header = ["#", "Rows", "Row Value", "Cols", "Col Value"].map(String::from);
+
+    let cells = breakdown
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            [
+                (i + 1).to_string(),
+                b.rows.to_string(),
+                format_value(&b.rows_value),
+                b.cols.to_string(),
+                format_value(&b.cols_value),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let widths = (0..header.len())
+        .map(|c| {
+            std::iter::once(&header)
+                .chain(cells.iter())
+                .map(|r| r[c].len())
+                .max()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    std::iter::once(&header)
+        .chain(cells.iter())
+        .map(|r| {
+            r.iter()
+                .zip(widths.iter())
+                .map(|(cell, w)| format!("{:<w$}", cell, w = w))
+                .collect::<Vec<_>>()
+                .join(" | ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn main() {
+    let registry = Registry::default();
+
+    let print = |label: &str, operations: &[Operation]| match evaluate(operations, &registry) {
+        Ok(total) => println!("{}: {}", label, total),
+        Err(e) => println!("{}: {:?}", label, e),
+    };
+
     let s_input = fs::read_to_string("sample_input.txt").unwrap();
-    println!("P1 sample: {:?}", operation_with_rows(s_input.as_str()));
-    println!("P2 sample: {:?}", operation_with_cols(s_input.as_str()));
+    let s_worksheet = Worksheet::from(s_input.as_str());
+    print("P1 sample", &s_worksheet.rows());
+    print("P2 sample", &s_worksheet.cols());
+
+    // cargo run -- table
+    let table = std::env::args().nth(1).is_some_and(|a| a == "table");
+
+    if table {
+        println!("{}\n", format_table(&s_worksheet.breakdown(&registry)));
+    }
 
     let input = fs::read_to_string("input.txt").unwrap();
-    println!("P1 input: {:?}", operation_with_rows(input.as_str()));
-    println!("P2 input: {:?}", operation_with_cols(input.as_str()));
+    let worksheet = Worksheet::from(input.as_str());
+    print("P1 input", &worksheet.rows());
+    print("P2 input", &worksheet.cols());
+
+    if table {
+        println!("{}", format_table(&worksheet.breakdown(&registry)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+123 328  51 64 
+ 45 64  387 23 
+  6 98  215 314
+*   +   *   +  
+";
+
+    fn operation(symbol: char, values: &[isize]) -> Operation {
+        Operation {
+            symbol: symbol.to_string(),
+            operands: values.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn sample() {
+        let registry = Registry::default();
+
+        let worksheet = Worksheet::from(SAMPLE);
+
+        assert_eq!(total(&worksheet.rows(), &registry), Ok(4277556));
+        assert_eq!(total(&worksheet.cols(), &registry), Ok(3263827));
+    }
+
+    #[test]
+    fn blocks() {
+        let worksheet = Worksheet::from(SAMPLE);
+
+        assert_eq!(worksheet.0.len(), 4);
+        assert_eq!(worksheet.0[1].operands, vec!["328", "64 ", "98 "]);
+        assert_eq!(worksheet.0[1].operator, "+");
+
+        let rows = worksheet.rows();
+        assert_eq!(rows[1].values(), Ok(vec![328, 64, 98]));
+
+        let cols = worksheet.cols();
+        assert_eq!(cols[0].symbol(), Ok('*'));
+        assert_eq!(cols[0].values(), Ok(vec![356, 24, 1]));
+        assert_eq!(cols[3].values(), Ok(vec![4, 431, 623]));
+    }
+
+    #[test]
+    fn table() {
+        let registry = Registry::default();
+        let worksheet = Worksheet::from(SAMPLE);
+
+        assert_eq!(
+            format_table(&worksheet.breakdown(&registry)),
+            "\
+# | Rows           | Row Value | Cols           | Col Value
+1 | 123 * 45 * 6   | 33210     | 356 * 24 * 1   | 8544
+2 | 328 + 64 + 98  | 490       | 8 + 248 + 369  | 625
+3 | 51 * 387 * 215 | 4243455   | 175 * 581 * 32 | 3253600
+4 | 64 + 23 + 314  | 401       | 4 + 431 + 623  | 1058"
+        );
+    }
+
+    #[test]
+    fn ragged_lines() {
+        // Trailing spaces trimmed off the shorter lines
+        let worksheet = Worksheet::from("12  3\n4   56\n+   *\n");
+
+        assert_eq!(worksheet.0.len(), 2);
+        assert_eq!(worksheet.rows()[1].values(), Ok(vec![3, 56]));
+        assert_eq!(worksheet.cols()[1].values(), Ok(vec![6, 35]));
+    }
+
+    #[test]
+    fn operators() {
+        let registry = Registry::default();
+
+        assert_eq!(operation('-', &[20, 5, 3]).calculate(&registry), Ok(12));
+        assert_eq!(operation('/', &[100, 5, 3]).calculate(&registry), Ok(6));
+        assert_eq!(operation('<', &[7, 2, 9]).calculate(&registry), Ok(2));
+        assert_eq!(operation('>', &[7, 2, 9]).calculate(&registry), Ok(9));
+        assert_eq!(operation('^', &[2, 3, 2]).calculate(&registry), Ok(512));
+    }
+
+    #[test]
+    fn errors() {
+        let registry = Registry::default();
+
+        assert_eq!(
+            operation('*', &[isize::MAX, 2]).calculate(&registry),
+            Err(CalcError::Overflow)
+        );
+        assert_eq!(
+            operation('+', &[isize::MAX, 1]).calculate(&registry),
+            Err(CalcError::Overflow)
+        );
+        assert_eq!(
+            operation('^', &[10, 19]).calculate(&registry),
+            Err(CalcError::Overflow)
+        );
+        assert_eq!(
+            operation('/', &[1, 0]).calculate(&registry),
+            Err(CalcError::DivideByZero)
+        );
+        assert_eq!(
+            operation('^', &[2, -1]).calculate(&registry),
+            Err(CalcError::NegativeExponent)
+        );
+        assert_eq!(
+            operation('%', &[1, 2]).calculate(&registry),
+            Err(CalcError::UnknownOperator('%'))
+        );
+        assert_eq!(
+            operation('+', &[]).calculate(&registry),
+            Err(CalcError::NoValues)
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let registry = Registry::default();
+
+        assert_eq!(parse_value("9223372036854775807"), Ok(isize::MAX));
+        assert_eq!(parse_value("-9223372036854775808"), Ok(isize::MIN));
+        assert_eq!(parse_value("9223372036854775808"), Err(CalcError::Overflow));
+        assert_eq!(
+            parse_value("1x"),
+            Err(CalcError::InvalidOperand(String::from("1x")))
+        );
+
+        // Twenty digit operand, as a row and as a column
+        let worksheet = Worksheet::from("99999999999999999999\n+\n");
+        assert_eq!(
+            worksheet.rows()[0].calculate(&registry),
+            Err(CalcError::Overflow)
+        );
+
+        let column = "9\n".repeat(20) + "+\n";
+        let worksheet = Worksheet::from(column.as_str());
+        assert_eq!(
+            worksheet.cols()[0].calculate(&registry),
+            Err(CalcError::Overflow)
+        );
+
+        let worksheet = Worksheet::from("1 2  3\n4 5  6\n+ **  \n");
+        assert_eq!(
+            worksheet.rows()[1].calculate(&registry),
+            Err(CalcError::InvalidOperator(String::from("**")))
+        );
+        assert_eq!(
+            worksheet.cols()[2].calculate(&registry),
+            Err(CalcError::MissingOperator)
+        );
+    }
+
+    #[test]
+    fn evaluate_exact() {
+        let registry = Registry::default();
+        let worksheet = Worksheet::from(SAMPLE);
+
+        assert_eq!(
+            evaluate(&worksheet.rows(), &registry),
+            Ok(Total::Exact(4277556))
+        );
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn evaluate_overflow() {
+        let registry = Registry::default();
+        let worksheet = Worksheet::from("9999999999\n9999999999\n*\n");
+
+        assert_eq!(
+            evaluate(&worksheet.rows(), &registry),
+            Err(CalcError::Overflow)
+        );
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn evaluate_big() {
+        let registry = Registry::default();
+
+        // 2^64 - 2^33 + 1, past 2^63 in a single problem
+        let worksheet = Worksheet::from("4294967295 3\n4294967295 4\n*          +\n");
+        assert_eq!(
+            evaluate(&worksheet.rows(), &registry),
+            Ok(Total::Big(
+                "18446744065119617032".parse::<BigInt>().unwrap()
+            ))
+        );
+
+        // Each problem fits, only the sum passes 2^63
+        let worksheet = Worksheet::from("9223372036854775807 1\n+                   +\n");
+        assert_eq!(
+            evaluate(&worksheet.rows(), &registry),
+            Ok(Total::Big(BigInt::from(isize::MAX) + 1))
+        );
+
+        // Operands that do not fit in `isize` on their own
+        let worksheet = Worksheet::from("99999999999999999999 1\n+                    +\n");
+        assert_eq!(
+            evaluate(&worksheet.rows(), &registry),
+            Ok(Total::Big(
+                "100000000000000000000".parse::<BigInt>().unwrap()
+            ))
+        );
+
+        let column = "99\n".repeat(20) + "+\n";
+        let worksheet = Worksheet::from(column.as_str());
+        assert_eq!(
+            evaluate(&worksheet.cols(), &registry),
+            Ok(Total::Big(
+                "199999999999999999998".parse::<BigInt>().unwrap()
+            ))
+        );
+
+        let worksheet = Worksheet::from("2  3\n70 1\n^  ^\n");
+        assert_eq!(
+            evaluate(&worksheet.rows(), &registry),
+            Ok(Total::Big(BigInt::from(2).pow(70) + 3))
+        );
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn big_errors() {
+        let registry = Registry::default();
+
+        assert_eq!(
+            operation('/', &[isize::MIN, -1, 0]).calculate_big(&registry),
+            Err(CalcError::DivideByZero)
+        );
+        assert_eq!(
+            operation('^', &[2, -1]).calculate_big(&registry),
+            Err(CalcError::NegativeExponent)
+        );
+    }
+
+    #[test]
+    fn custom_operator() {
+        let mut registry = Registry::default();
+        registry.register(
+            '%',
+            Operator::left(|a, b| a.checked_rem(b).ok_or(CalcError::DivideByZero)),
+        );
+
+        let worksheet = Worksheet::from("17 9\n5  4\n%  -\n");
+
+        assert_eq!(total(&worksheet.rows(), &registry), Ok(2 + 5));
+        assert_eq!(operation('%', &[17, 5]).calculate(&registry), Ok(2));
+    }
 }
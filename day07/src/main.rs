@@ -1,4 +1,45 @@
+use std::collections::HashSet;
 use std::fs;
+use std::io::{self, Write};
+
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    fn delta(&self) -> (isize, isize) {
+        match self {
+            Self::Up => (-1, 0),
+            Self::Down => (1, 0),
+            Self::Left => (0, -1),
+            Self::Right => (0, 1),
+        }
+    }
+
+    fn opposite(&self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    /// Both directions at right angles to this one
+    fn sides(&self) -> [Self; 2] {
+        match self {
+            Self::Up | Self::Down => [Self::Left, Self::Right],
+            Self::Left | Self::Right => [Self::Up, Self::Down],
+        }
+    }
+}
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Tile {
@@ -6,6 +47,13 @@ enum Tile {
     Beam,
     Splitter,
     Start,
+    /// `/`
+    MirrorForward,
+    /// `\`
+    MirrorBack,
+    Absorber,
+    /// Sends every beam that enters it one way, and blocks beams coming the other way
+    Deflector(Direction),
 }
 
 impl std::fmt::Debug for Tile {
@@ -15,6 +63,13 @@ impl std::fmt::Debug for Tile {
             Self::Beam => "|",
             Self::Splitter => "^",
             Self::Start => "S",
+            Self::MirrorForward => "/",
+            Self::MirrorBack => "\\",
+            Self::Absorber => "#",
+            Self::Deflector(Direction::Left) => "<",
+            Self::Deflector(Direction::Right) => ">",
+            Self::Deflector(Direction::Up) => "A",
+            Self::Deflector(Direction::Down) => "V",
         })
     }
 }
@@ -25,11 +80,119 @@ impl From<char> for Tile {
             '.' => Self::Empty,
             'S' => Self::Start,
             '^' => Self::Splitter,
+            '/' => Self::MirrorForward,
+            '\\' => Self::MirrorBack,
+            '#' => Self::Absorber,
+            '<' => Self::Deflector(Direction::Left),
+            '>' => Self::Deflector(Direction::Right),
+            'A' => Self::Deflector(Direction::Up),
+            'V' => Self::Deflector(Direction::Down),
             _ => panic!("Invalid character {:?}", value),
         }
     }
 }
 
+impl Tile {
+    /// Direction a beam leaves this tile in after entering it travelling `direction`, or
+    /// `None` if the tile stops it. Splitters are handled by `Grid::enter`.
+    fn redirect(&self, direction: Direction) -> Option<Direction> {
+        use Direction::*;
+
+        match (self, direction) {
+            (Self::Absorber, _) => None,
+            (Self::MirrorForward, Right) => Some(Up),
+            (Self::MirrorForward, Up) => Some(Right),
+            (Self::MirrorForward, Left) => Some(Down),
+            (Self::MirrorForward, Down) => Some(Left),
+            (Self::MirrorBack, Right) => Some(Down),
+            (Self::MirrorBack, Down) => Some(Right),
+            (Self::MirrorBack, Left) => Some(Up),
+            (Self::MirrorBack, Up) => Some(Left),
+            (Self::Deflector(d), _) if direction == d.opposite() => None,
+            (Self::Deflector(d), _) => Some(*d),
+            _ => Some(direction),
+        }
+    }
+}
+
+type Position = (usize, usize);
+
+/// A beam occupying a cell and about to move on in a direction
+type BeamState = (Position, Direction);
+
+/// Outcome of following every beam from the start through a manifold
+#[derive(Debug, Default)]
+struct Simulation {
+    activated: HashSet<Position>,
+    energized: HashSet<Position>,
+    /// A beam came back to a cell it had already passed through in the same direction
+    cycle: bool,
+}
+
+/// Timeline counter for `unique_paths`. Fixed width counters panic rather than wrap when the
+/// count no longer fits.
+trait PathCount: Clone {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn accumulate(&mut self, other: &Self);
+    fn log2(&self) -> f64;
+}
+
+macro_rules! fixed_path_count {
+    ($($t:ty),*) => {$(
+        impl PathCount for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn one() -> Self {
+                1
+            }
+
+            fn accumulate(&mut self, other: &Self) {
+                *self = self.checked_add(*other).unwrap_or_else(|| {
+                    panic!("Path count overflowed {}", stringify!($t))
+                });
+            }
+
+            fn log2(&self) -> f64 {
+                (*self as f64).log2()
+            }
+        }
+    )*};
+}
+
+fixed_path_count!(usize, u64, u128);
+
+#[cfg(feature = "bigint")]
+impl PathCount for BigUint {
+    fn zero() -> Self {
+        BigUint::ZERO
+    }
+
+    fn one() -> Self {
+        BigUint::from(1u8)
+    }
+
+    fn accumulate(&mut self, other: &Self) {
+        *self += other;
+    }
+
+    fn log2(&self) -> f64 {
+        // Only the leading 64 bits matter at f64 precision
+        let shift = self.bits().saturating_sub(64);
+        let top = (self >> shift).iter_u64_digits().next().unwrap_or(0);
+        (top as f64).log2() + shift as f64
+    }
+}
+
+/// Bottom row columns reached from one start, with the timelines reaching each
+#[derive(Debug, PartialEq)]
+struct ExitReport<T> {
+    source: Position,
+    exits: Vec<(usize, T)>,
+}
+
 #[derive(Clone)]
 struct Grid(Vec<Vec<Tile>>);
 
@@ -57,7 +220,7 @@ impl From<&str> for Grid {
             value
                 .split("\n")
                 .filter(|l| !l.is_empty())
-                .map(|l| l.chars().map(|c| Tile::from(c)).collect::<Vec<_>>())
+                .map(|l| l.chars().map(Tile::from).collect::<Vec<_>>())
                 .collect::<Vec<_>>(),
         )
     }
@@ -65,102 +228,396 @@ impl From<&str> for Grid {
 
 impl Grid {
     fn get(&self, row: isize, col: isize) -> Option<Tile> {
-        let get_owned = |v: &Vec<Tile>, c: usize| match v.get(c) {
-            Some(t) => Some(*t),
-            None => None,
-        };
-
         let row_negative = row < 0;
         let col_negative = col < 0;
 
         match (row_negative, col_negative) {
             (true, _) => None,
             (_, true) => None,
-            _ => match self.0.get(row as usize) {
-                None => None,
-                Some(v) => get_owned(v, col as usize),
-            },
+            _ => self
+                .0
+                .get(row as usize)
+                .and_then(|v| v.get(col as usize).copied()),
         }
     }
 
-    fn find_start(&self) -> Option<(usize, usize)> {
+    /// Every `S` in reading order
+    fn find_starts(&self) -> Vec<Position> {
+        let mut starts = Vec::new();
+
         for row in 0..self.0.len() {
             for col in 0..self.0[row].len() {
                 if self.0[row][col] == Tile::Start {
-                    return Some((row, col));
+                    starts.push((row, col));
                 }
             }
         }
 
-        None
+        assert!(!starts.is_empty(), "Manifold has no start");
+        starts
     }
 
-    fn trace(mut self) -> (Self, usize) {
-        let (row, col) = self.find_start().unwrap();
-        let result = self.trace_from(row, col);
-        (self, result)
+    /// Number of splitters hit. Beams that converge on a splitter only activate it once.
+    fn trace(self) -> (Self, usize) {
+        if self.is_downward() {
+            let (grid, activated) = self.trace_splitters();
+            return (grid, activated.len());
+        }
+
+        let mut grid = self;
+        let simulation = grid.simulate();
+
+        for &(r, c) in simulation.energized.iter() {
+            if matches!(grid.0[r][c], Tile::Empty | Tile::Start) {
+                grid.0[r][c] = Tile::Beam;
+            }
+        }
+
+        (grid, simulation.activated.len())
     }
 
-    fn trace_from(&mut self, row: usize, col: usize) -> usize {
-        self.0[row][col] = Tile::Beam;
+    /// True when every beam can only travel down, as `trace_splitters` and `unique_paths`
+    /// assume
+    fn is_downward(&self) -> bool {
+        self.0
+            .iter()
+            .flatten()
+            .all(|t| matches!(t, Tile::Empty | Tile::Beam | Tile::Splitter | Tile::Start))
+    }
 
-        match self.get(row as isize + 1, col as isize) {
-            None => 0,
-            Some(Tile::Empty) => self.trace_from(row + 1, col),
-            Some(Tile::Splitter) => {
-                let left = match self.get(row as isize + 1, col as isize - 1) {
-                    Some(_) => self.trace_from(row + 1, col - 1),
-                    None => 0,
-                };
+    /// Cell one step from `position`, if it is on the grid
+    fn step(&self, position: Position, direction: Direction) -> Option<Position> {
+        let (dr, dc) = direction.delta();
+        let (r, c) = (position.0 as isize + dr, position.1 as isize + dc);
 
-                let right = match self.get(row as isize + 1, col as isize + 1) {
-                    Some(_) => self.trace_from(row + 1, col + 1),
-                    None => 0,
-                };
-                left + right + 1
+        self.get(r, c).map(|_| (r as usize, c as usize))
+    }
+
+    /// Beams produced by a beam travelling `direction` moving into `position`. A splitter
+    /// sends beams out of the cells either side of it, still travelling `direction`, which
+    /// for a downward beam is the original manifold behaviour.
+    fn enter(&self, position: Position, direction: Direction) -> Vec<BeamState> {
+        let land = |p: Position| {
+            let (r, c) = p;
+            match self.0[r][c] {
+                Tile::Splitter => Some((p, direction)),
+                t => t.redirect(direction).map(|d| (p, d)),
             }
-            Some(_) => 0,
+        };
+
+        match self.0[position.0][position.1] {
+            Tile::Splitter => direction
+                .sides()
+                .iter()
+                .filter_map(|&side| self.step(position, side))
+                .filter_map(land)
+                .collect(),
+            _ => land(position).into_iter().collect(),
         }
     }
 
-    fn unique_paths(mut self) -> (Self, usize) {
-        let (row, col) = self.find_start().unwrap();
+    /// Follows beams in any direction from the start, without recursion. Depth first so a
+    /// beam returning to a state still on the current path can be reported as a cycle.
+    fn simulate(&self) -> Simulation {
+        let mut simulation = Simulation::default();
 
-        let result =
-            self.unique_paths_from(row, col, &mut vec![vec![0; self.0[0].len()]; self.0.len()]);
+        let mut visited = HashSet::new();
+        let mut on_path = HashSet::new();
+        let mut stack = self
+            .find_starts()
+            .into_iter()
+            .map(|start| ((start, Direction::Down), false))
+            .collect::<Vec<_>>();
+
+        while let Some((state, expanded)) = stack.pop() {
+            if expanded {
+                on_path.remove(&state);
+                continue;
+            }
+
+            if on_path.contains(&state) {
+                simulation.cycle = true;
+                continue;
+            }
 
-        (self, result)
+            if !visited.insert(state) {
+                continue;
+            }
+
+            let (position, direction) = state;
+            simulation.energized.insert(position);
+            on_path.insert(state);
+            stack.push((state, true));
+
+            let Some(next) = self.step(position, direction) else {
+                continue;
+            };
+
+            if self.0[next.0][next.1] == Tile::Splitter {
+                simulation.activated.insert(next);
+            }
+
+            for beam in self.enter(next, direction) {
+                stack.push((beam, false));
+            }
+        }
+
+        simulation
     }
 
-    fn unique_paths_from(&mut self, row: usize, col: usize, cache: &mut Vec<Vec<usize>>) -> usize {
-        self.0[row][col] = Tile::Beam;
+    /// Sweeps down one row at a time carrying the set of columns holding a beam, so tall
+    /// manifolds need no recursion. Returns the position of every splitter a beam reached.
+    fn trace_splitters(mut self) -> (Self, HashSet<(usize, usize)>) {
+        let starts = self.find_starts();
+        let width = self.width();
+
+        let mut beams = vec![false; width];
+        let mut activated = HashSet::new();
+
+        for r in starts[0].0..self.0.len() {
+            for &(_, col) in starts.iter().filter(|s| s.0 == r) {
+                beams[col] = true;
+            }
+
+            let mut next = vec![false; width];
+
+            for c in (0..width).filter(|&c| beams[c]) {
+                self.0[r][c] = Tile::Beam;
 
-        let result = match self.get(row as isize + 1, col as isize) {
-            None => 1,
-            Some(Tile::Splitter) => {
-                let cached = cache[row + 1][col];
+                match self.get(r as isize + 1, c as isize) {
+                    None => {}
+                    Some(Tile::Splitter) => {
+                        activated.insert((r + 1, c));
 
-                if cached > 0 {
-                    return cached;
+                        for side in self.split_sides(r + 1, c) {
+                            next[side] = true;
+                        }
+                    }
+                    Some(_) => next[c] = true,
                 }
+            }
+
+            beams = next;
+        }
+
+        (self, activated)
+    }
+
+    /// Timelines from every start, added together. Beams are only drawn in once every start
+    /// has been counted, so no start sees another's beams in place of a splitter.
+    fn unique_paths<T: PathCount>(mut self) -> (Self, T) {
+        let mut total = T::zero();
+        let mut lit = Vec::new();
+
+        for start in self.find_starts() {
+            for count in self
+                .paths_from::<T>(start, &mut |position, _| lit.push(position))
+                .iter()
+                .flatten()
+            {
+                total.accumulate(count);
+            }
+        }
+
+        for (r, c) in lit {
+            self.0[r][c] = Tile::Beam;
+        }
+
+        (self, total)
+    }
+
+    /// Same sweep as `trace`, carrying the number of timelines in each column instead. Columns
+    /// no beam has reached are `None`, kept apart from a counted zero. Returns the timelines
+    /// reaching each column of the bottom row, and calls `visit` for every cell a beam passes
+    /// through.
+    fn paths_from<T: PathCount>(
+        &self,
+        start: Position,
+        visit: &mut impl FnMut(Position, &T),
+    ) -> Vec<Option<T>> {
+        assert!(
+            self.is_downward(),
+            "unique_paths only supports manifolds where beams travel down"
+        );
 
-                let left = match self.get(row as isize + 1, col as isize - 1) {
-                    Some(_) => self.unique_paths_from(row + 1, col - 1, cache),
-                    None => 0,
+        let (row, col) = start;
+        let width = self.width();
+
+        let mut paths = vec![None; width];
+        paths[col] = Some(T::one());
+
+        for r in row..self.0.len() - 1 {
+            let mut next: Vec<Option<T>> = vec![None; width];
+
+            for (c, count) in paths.iter().enumerate() {
+                let Some(count) = count else {
+                    continue;
                 };
 
-                let right = match self.get(row as isize + 1, col as isize + 1) {
-                    Some(_) => self.unique_paths_from(row + 1, col + 1, cache),
-                    None => 0,
+                visit((r, c), count);
+
+                let targets = match self.get(r as isize + 1, c as isize) {
+                    None => Vec::new(),
+                    Some(Tile::Splitter) => self.split_sides(r + 1, c).collect(),
+                    Some(_) => vec![c],
                 };
-                cache[row + 1][col] = left + right;
-                left + right
+
+                for target in targets {
+                    next[target].get_or_insert_with(T::zero).accumulate(count);
+                }
             }
-            Some(_) => self.unique_paths_from(row + 1, col, cache),
-        };
 
-        result
+            paths = next;
+        }
+
+        let last = self.0.len() - 1;
+
+        for (c, count) in paths.iter().enumerate() {
+            if let Some(count) = count {
+                visit((last, c), count);
+            }
+        }
+
+        paths
+    }
+
+    /// For each start, the bottom row columns its beams leave through and how many
+    /// timelines take each one
+    fn exit_report<T: PathCount>(&self) -> Vec<ExitReport<T>> {
+        self.find_starts()
+            .into_iter()
+            .map(|source| ExitReport {
+                source,
+                exits: self
+                    .paths_from::<T>(source, &mut |_, _| {})
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(col, count)| count.map(|n| (col, n)))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Timelines passing through each cell, from every start
+    fn heat_map<T: PathCount>(&self) -> Vec<Vec<Option<T>>> {
+        let mut heat = self
+            .0
+            .iter()
+            .map(|r| vec![None; r.len()])
+            .collect::<Vec<Vec<Option<T>>>>();
+
+        for start in self.find_starts() {
+            self.paths_from::<T>(start, &mut |(r, c), count| {
+                heat[r][c].get_or_insert_with(T::zero).accumulate(count);
+            });
+        }
+
+        heat
+    }
+
+    fn width(&self) -> usize {
+        self.0.iter().map(|r| r.len()).max().unwrap_or(0)
+    }
+
+    /// Columns either side of the splitter at `(row, col)` that are still on the grid
+    fn split_sides(&self, row: usize, col: usize) -> impl Iterator<Item = usize> {
+        [col as isize - 1, col as isize + 1]
+            .into_iter()
+            .filter(move |&c| self.get(row as isize, c).is_some())
+            .map(|c| c as usize)
+    }
+}
+
+/// Colour for a cell `t` of the way from the coolest to the hottest, dark blue through red to
+/// yellow
+fn heat_colour(t: f64) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0);
+    let channel = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    [channel(t * 2.0), channel(t * 2.0 - 1.0), channel(0.6 - t)]
+}
+
+/// Heat colour for every cell a timeline reaches, on a log scale since counts grow
+/// exponentially with splitter depth
+fn shade<T: PathCount>(heat: &[Vec<Option<T>>]) -> Vec<Vec<Option<[u8; 3]>>> {
+    let hottest = heat
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|n| n.log2())
+        .fold(0.0, f64::max);
+
+    heat.iter()
+        .map(|row| {
+            row.iter()
+                .map(|n| {
+                    n.as_ref().map(|n| match hottest {
+                        0.0 => heat_colour(1.0),
+                        _ => heat_colour(n.log2() / hottest),
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Traced grid with each beam cell coloured by its shade, for a 24-bit colour terminal
+fn render_ansi(grid: &Grid, shading: &[Vec<Option<[u8; 3]>>]) -> String {
+    grid.0
+        .iter()
+        .enumerate()
+        .map(|(r, row)| {
+            row.iter()
+                .enumerate()
+                .map(
+                    |(c, tile)| match shading.get(r).and_then(|s| s.get(c)).copied().flatten() {
+                        Some([red, green, blue]) => {
+                            format!("\x1b[38;2;{};{};{}m{:?}\x1b[0m", red, green, blue, tile)
+                        }
+                        None => format!("{:?}", tile),
+                    },
+                )
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Traced grid as a binary PPM with each cell drawn as a `scale` x `scale` block
+fn write_ppm<W: Write>(
+    grid: &Grid,
+    shading: &[Vec<Option<[u8; 3]>>],
+    scale: usize,
+    out: &mut W,
+) -> io::Result<()> {
+    let height = grid.0.len();
+    let width = grid.width();
+
+    write!(out, "P6\n{} {}\n255\n", width * scale, height * scale)?;
+
+    for (r, row) in grid.0.iter().enumerate() {
+        let line = (0..width)
+            .flat_map(|c| {
+                let shaded = shading.get(r).and_then(|s| s.get(c)).copied().flatten();
+
+                let colour = match (shaded, row.get(c)) {
+                    (Some(colour), _) => colour,
+                    (None, Some(Tile::Empty) | None) => [0, 0, 0],
+                    (None, Some(Tile::Beam)) => [90, 90, 90],
+                    (None, Some(_)) => [255, 255, 255],
+                };
+
+                std::iter::repeat_n(colour, scale)
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+
+        for _ in 0..scale {
+            out.write_all(&line)?;
+        }
     }
+
+    Ok(())
 }
 
 fn main() {
@@ -168,6 +625,384 @@ fn main() {
 
     let grid = Grid::from(input.as_str());
 
+    // cargo run -- render [out.ppm]
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if args.first().is_some_and(|a| a == "render") {
+        let shading = match grid.is_downward() {
+            true => shade(&grid.heat_map::<u128>()),
+            false => Vec::new(),
+        };
+        let (traced, _) = grid.trace();
+
+        println!("{}", render_ansi(&traced, &shading));
+
+        if let Some(path) = args.get(1) {
+            let mut file = io::BufWriter::new(fs::File::create(path).unwrap());
+            write_ppm(&traced, &shading, 4, &mut file).unwrap();
+        }
+
+        return;
+    }
+
     println!("Pt 1: {:?}", grid.clone().trace().1);
-    println!("Pt 2: {:?}", grid.unique_paths().1);
+
+    if !grid.is_downward() {
+        let simulation = grid.simulate();
+        println!(
+            "Energized: {}, Cycle: {}",
+            simulation.energized.len(),
+            simulation.cycle
+        );
+        return;
+    }
+
+    println!("Pt 2: {:?}", grid.clone().unique_paths::<u128>().1);
+
+    for report in grid.exit_report::<u128>() {
+        println!(
+            "Source {:?} exits: {}",
+            report.source,
+            report
+                .exits
+                .iter()
+                .map(|(col, n)| format!("{} (x{})", col, n))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    #[cfg(feature = "bigint")]
+    println!("Pt 2 (big): {}", grid.unique_paths::<BigUint>().1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+.......S.......
+...............
+.......^.......
+...............
+......^.^......
+...............
+.....^.^.^.....
+...............
+....^.^...^....
+...............
+...^.^...^.^...
+...............
+..^...^.....^..
+...............
+.^.^.^.^.^...^.
+...............
+";
+
+    #[test]
+    fn sample() {
+        let grid = Grid::from(SAMPLE);
+
+        assert_eq!(grid.clone().trace().1, 21);
+        assert_eq!(grid.unique_paths::<usize>().1, 40);
+    }
+
+    #[test]
+    fn traced_grid() {
+        let (grid, splits) = Grid::from("..S..\n.....\n..^..\n.....\n").trace();
+
+        assert_eq!(splits, 1);
+        assert_eq!(format!("{:?}", grid), "..|..\n..|..\n.|^|.\n.|.|.");
+    }
+
+    #[test]
+    fn converging_beams() {
+        // Beams from two splitters merge in column 3 before reaching the last splitter
+        let grid = Grid::from(
+            "\
+...S...
+.......
+...^...
+.......
+..^.^..
+.......
+...^...
+.......
+",
+        );
+
+        let (_, activated) = grid.clone().trace_splitters();
+
+        assert_eq!(activated, HashSet::from([(2, 3), (4, 2), (4, 4), (6, 3)]));
+        assert_eq!(grid.clone().trace().1, 4);
+        assert_eq!(grid.unique_paths::<usize>().1, 6);
+    }
+
+    #[test]
+    fn converging_on_adjacent_rows() {
+        // No gap rows: both sides of row 2 land in column 2 directly above a splitter
+        let grid = Grid::from("..S..\n..^..\n.^.^.\n..^..\n.....\n");
+
+        let (_, activated) = grid.clone().trace_splitters();
+
+        assert_eq!(activated, HashSet::from([(1, 2), (2, 1), (2, 3), (3, 2)]));
+        assert_eq!(grid.trace().1, 4);
+    }
+
+    // Every pair of splitter rows doubles the timelines in the middle column
+    fn doubling_grid(pairs: usize) -> Grid {
+        let mut input = String::from(".S.\n");
+        input.push_str(&".^.\n^.^\n".repeat(pairs));
+        input.push_str("...\n");
+
+        Grid::from(input.as_str())
+    }
+
+    #[test]
+    fn wide_counters() {
+        assert_eq!(doubling_grid(10).unique_paths::<usize>().1, 1 << 10);
+        assert_eq!(doubling_grid(70).unique_paths::<u128>().1, 1 << 70);
+    }
+
+    #[test]
+    #[should_panic(expected = "Path count overflowed u64")]
+    fn fixed_counter_overflow() {
+        doubling_grid(70).unique_paths::<u64>();
+    }
+
+    #[test]
+    fn unreachable_columns_stay_unvisited() {
+        // Beams fall off both edges, leaving no timelines but no panic either
+        let (grid, paths) = Grid::from(".S.\n.^.\n^.^\n...\n").unique_paths::<usize>();
+
+        assert_eq!(paths, 2);
+        assert_eq!(format!("{:?}", grid), ".|.\n|^|\n^|^\n.|.");
+
+        let (_, paths) = Grid::from("S\n^\n.\n").unique_paths::<usize>();
+        assert_eq!(paths, 0);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn big_counter() {
+        assert_eq!(
+            doubling_grid(70).unique_paths::<BigUint>().1,
+            BigUint::from(1u128 << 70)
+        );
+        assert_eq!(
+            doubling_grid(200).unique_paths::<BigUint>().1,
+            BigUint::from(1u8) << 200usize
+        );
+    }
+
+    #[test]
+    fn simulate_matches_sweep() {
+        for input in [
+            SAMPLE,
+            "...S...\n.......\n...^...\n.......\n..^.^..\n.......\n...^...\n.......\n",
+            "..S..\n..^..\n.^.^.\n..^..\n.....\n",
+        ] {
+            let grid = Grid::from(input);
+
+            assert_eq!(grid.simulate().activated, grid.clone().trace_splitters().1);
+            assert!(!grid.simulate().cycle);
+        }
+    }
+
+    #[test]
+    fn mirrors_and_absorbers() {
+        // Down, right off the `\`, up off the `/`, then split sideways by the `^`
+        let grid = Grid::from("..^#.\n.....\nS..#.\n\\./..\n");
+
+        let simulation = grid.simulate();
+
+        assert_eq!(simulation.activated, HashSet::from([(0, 2)]));
+        assert!(!simulation.cycle);
+        assert!(simulation.energized.contains(&(3, 1)));
+        assert!(simulation.energized.contains(&(0, 1)));
+        assert!(!simulation.energized.contains(&(0, 3)));
+        assert!(!simulation.energized.contains(&(2, 4)));
+    }
+
+    #[test]
+    fn deflectors_are_one_way() {
+        // `>` turns the downward beam right; the `<` it then meets blocks it
+        let grid = Grid::from(".S...\n.>..<\n.....\n");
+        let simulation = grid.simulate();
+
+        assert!(simulation.energized.contains(&(1, 3)));
+        assert!(!simulation.energized.contains(&(1, 4)));
+        assert!(!simulation.energized.contains(&(2, 1)));
+
+        // Entered from above, `<` turns the beam left instead
+        let grid = Grid::from("...S\n...<\n....\n");
+        let simulation = grid.simulate();
+
+        assert!(simulation.energized.contains(&(1, 0)));
+        assert!(!simulation.energized.contains(&(2, 3)));
+
+        // Every glyph reads back as the tile it was drawn from
+        let input = ".S.<\n/\\#>\n.A^V";
+        assert_eq!(format!("{:?}", Grid::from(input)), input);
+    }
+
+    #[test]
+    fn cycles() {
+        // The left half of the split runs round the four mirrors and back into itself
+        let grid = Grid::from("..S..\n./..\\\n..^..\n.\\../\n");
+
+        let (traced, splits) = grid.clone().trace();
+        let simulation = grid.simulate();
+
+        assert!(simulation.cycle);
+        assert_eq!(splits, 1);
+        assert_eq!(format!("{:?}", traced), "..|..\n./||\\\n.|^||\n.\\||/");
+    }
+
+    #[test]
+    #[should_panic(expected = "beams travel down")]
+    fn unique_paths_rejects_mirrors() {
+        Grid::from("S\n/\n").unique_paths::<usize>();
+    }
+
+    #[test]
+    fn multiple_sources() {
+        // Each source is split twice, then both feed the shared splitter in column 3
+        let grid = Grid::from(
+            "\
+.S.....
+.....S.
+.^...^.
+.......
+..^.^..
+.......
+...^...
+.......
+",
+        );
+
+        let (_, activated) = grid.clone().trace_splitters();
+        assert_eq!(
+            activated,
+            HashSet::from([(2, 1), (2, 5), (4, 2), (4, 4), (6, 3)])
+        );
+        assert_eq!(grid.simulate().activated, activated);
+
+        assert_eq!(
+            grid.exit_report::<usize>(),
+            vec![
+                ExitReport {
+                    source: (0, 1),
+                    exits: vec![(0, 1), (1, 1), (2, 1), (4, 1)],
+                },
+                ExitReport {
+                    source: (1, 5),
+                    exits: vec![(2, 1), (4, 1), (5, 1), (6, 1)],
+                },
+            ]
+        );
+        assert_eq!(grid.unique_paths::<usize>().1, 8);
+    }
+
+    #[test]
+    fn sources_do_not_interfere() {
+        // The second source's splitter is where the first source's beam lands from the side
+        let grid = Grid::from(".SS...\n.^^...\n......\n");
+        let alone = Grid::from("..S...\n.^^...\n......\n");
+
+        assert_eq!(alone.exit_report::<usize>()[0].exits, vec![(1, 1), (3, 1)]);
+        assert_eq!(
+            grid.exit_report::<usize>(),
+            vec![
+                ExitReport {
+                    source: (0, 1),
+                    exits: vec![(0, 1), (2, 1)],
+                },
+                ExitReport {
+                    source: (0, 2),
+                    exits: vec![(1, 1), (3, 1)],
+                },
+            ]
+        );
+        assert_eq!(grid.unique_paths::<usize>().1, 4);
+    }
+
+    #[test]
+    fn heat_map() {
+        let grid = Grid::from(".S.\n.^.\n...\n^.^\n...\n");
+
+        let heat = grid.heat_map::<usize>();
+
+        assert_eq!(
+            heat,
+            vec![
+                vec![None, Some(1), None],
+                vec![Some(1), None, Some(1)],
+                vec![Some(1), None, Some(1)],
+                vec![None, Some(2), None],
+                vec![None, Some(2), None],
+            ]
+        );
+    }
+
+    #[test]
+    fn heat_map_multiple_sources() {
+        // Each source splits on its own splitter, including the one the other lands beside
+        let grid = Grid::from(".SS...\n.^^...\n......\n");
+
+        assert_eq!(
+            grid.heat_map::<usize>(),
+            vec![
+                vec![None, Some(1), Some(1), None, None, None],
+                vec![Some(1), Some(1), Some(1), Some(1), None, None],
+                vec![Some(1), Some(1), Some(1), Some(1), None, None],
+            ]
+        );
+    }
+
+    #[test]
+    fn rendering() {
+        let grid = Grid::from(".S.\n.^.\n...\n^.^\n...\n");
+        let shading = shade(&grid.heat_map::<usize>());
+        let (traced, _) = grid.trace();
+
+        // One timeline reaches the upper beams and two meet below the second row of splitters
+        let (cool, hot) = (heat_colour(0.0), heat_colour(1.0));
+        assert_eq!(shading[2], vec![Some(cool), None, Some(cool)]);
+        assert_eq!(shading[3], vec![None, Some(hot), None]);
+
+        let ansi = render_ansi(&traced, &shading);
+        assert_eq!(
+            ansi.lines().nth(3),
+            Some(format!("^\x1b[38;2;{};{};{}m|\x1b[0m^", hot[0], hot[1], hot[2]).as_str())
+        );
+
+        let mut ppm = Vec::new();
+        write_ppm(&traced, &shading, 1, &mut ppm).unwrap();
+
+        // Shaded cells take their heat colour, unlit splitters are white and empty cells black
+        let pixels = &ppm[ppm.len() - 5 * 3 * 3..];
+        let pixel = |r: usize, c: usize| &pixels[(r * 3 + c) * 3..][..3];
+        assert_eq!(pixel(0, 1), cool);
+        assert_eq!(pixel(2, 2), cool);
+        assert_eq!(pixel(4, 1), hot);
+        assert_eq!(pixel(3, 0), [255, 255, 255]);
+        assert_eq!(pixel(4, 0), [0, 0, 0]);
+        assert!(hot[0] > cool[0]);
+    }
+
+    #[test]
+    fn tall_manifold() {
+        let rows = 300_000;
+        let mut input = String::from("..S..\n");
+
+        for r in 0..rows {
+            input.push_str(if r % 100 == 50 { "..^..\n" } else { ".....\n" });
+        }
+
+        let grid = Grid::from(input.as_str());
+
+        assert_eq!(grid.clone().trace().1, 1);
+        assert_eq!(grid.unique_paths::<usize>().1, 2);
+    }
 }
@@ -1,7 +1,8 @@
 use std::{
     cmp::Ordering,
-    collections::{BinaryHeap, HashMap},
+    collections::{BTreeMap, BinaryHeap},
     fs,
+    io::{self, Write},
 };
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -15,15 +16,19 @@ impl From<&str> for Point {
 }
 
 impl Point {
-    fn to_f64(&self) -> (f64, f64, f64) {
-        (f64::from(self.0), f64::from(self.1), f64::from(self.2))
+    fn axis(self, axis: usize) -> i32 {
+        match axis % 3 {
+            0 => self.0,
+            1 => self.1,
+            _ => self.2,
+        }
     }
 
-    fn euclid_distance(&self, other: &Self) -> f64 {
-        let p1 = self.to_f64();
-        let p2 = other.to_f64();
-
-        ((p2.0 - p1.0).powi(2) + (p2.1 - p1.1).powi(2) + (p2.2 - p1.2).powi(2)).sqrt()
+    /// Exact squared distance, which cannot overflow for any pair of `i32` points
+    fn squared_distance(self, other: Self) -> i128 {
+        (0..3)
+            .map(|a| i128::from(i64::from(self.axis(a)) - i64::from(other.axis(a))).pow(2))
+            .sum()
     }
 }
 
@@ -31,12 +36,19 @@ impl Point {
 struct PointPair {
     p1: Point,
     p2: Point,
-    d: f64,
+    /// Indices of `p1` and `p2` in the input
+    i1: usize,
+    i2: usize,
+    /// Squared distance between `p1` and `p2`
+    d: i128,
 }
 
 impl Ord for PointPair {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.d.total_cmp(&other.d).reverse()
+        // Ties on distance fall back to input order so the sequence is deterministic
+        (self.d, self.i1, self.i2)
+            .cmp(&(other.d, other.i1, other.i2))
+            .reverse()
     }
 }
 
@@ -48,144 +60,430 @@ impl PartialOrd for PointPair {
 
 impl PartialEq for PointPair {
     fn eq(&self, other: &Self) -> bool {
-        (self.p1 == other.p1 && self.p2 == other.p2) || (self.p1 == other.p2 && self.p2 == other.p1)
+        self.cmp(other).is_eq()
     }
 }
 
 impl Eq for PointPair {}
 
 impl PointPair {
-    fn new(p1: Point, p2: Point) -> Self {
+    fn new(points: &[Point], i1: usize, i2: usize) -> Self {
+        let (p1, p2) = (points[i1], points[i2]);
+
         Self {
             p1,
             p2,
-            d: p1.euclid_distance(&p2),
+            i1,
+            i2,
+            d: p1.squared_distance(p2),
+        }
+    }
+}
+
+/// Bounding box and largest point index of a k-d tree subtree
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min: [i32; 3],
+    max: [i32; 3],
+    max_index: usize,
+}
+
+impl Bounds {
+    fn new(point: Point, index: usize) -> Self {
+        let p = [point.0, point.1, point.2];
+        Self {
+            min: p,
+            max: p,
+            max_index: index,
         }
     }
+
+    fn merge(self, other: Option<Self>) -> Self {
+        let Some(other) = other else {
+            return self;
+        };
+
+        Self {
+            min: std::array::from_fn(|a| self.min[a].min(other.min[a])),
+            max: std::array::from_fn(|a| self.max[a].max(other.max[a])),
+            max_index: self.max_index.max(other.max_index),
+        }
+    }
+
+    /// Closest and furthest squared distance from `point` to anything inside the box
+    fn distance_range(&self, point: Point) -> (i128, i128) {
+        let (mut near, mut far) = (0, 0);
+
+        for a in 0..3 {
+            let (q, lo, hi) = (
+                i128::from(point.axis(a)),
+                i128::from(self.min[a]),
+                i128::from(self.max[a]),
+            );
+            near += (lo - q).max(q - hi).max(0).pow(2);
+            far += (q - lo).abs().max((hi - q).abs()).pow(2);
+        }
+
+        (near, far)
+    }
 }
 
-struct PointHeapIter(BinaryHeap<PointPair>);
+/// Implicit k-d tree over point indices
+struct KdTree {
+    nodes: Vec<usize>,
+    bounds: Vec<Bounds>,
+}
+
+impl KdTree {
+    fn new(points: &[Point]) -> Self {
+        let mut tree = Self {
+            nodes: (0..points.len()).collect(),
+            bounds: points
+                .iter()
+                .enumerate()
+                .map(|(i, &p)| Bounds::new(p, i))
+                .collect(),
+        };
+        tree.build(points, 0, points.len(), 0);
+
+        tree
+    }
+
+    fn build(&mut self, points: &[Point], lo: usize, hi: usize, depth: usize) -> Option<Bounds> {
+        if lo >= hi {
+            return None;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+        self.nodes[lo..hi].select_nth_unstable_by_key(mid - lo, |&i| points[i].axis(depth));
+
+        let left = self.build(points, lo, mid, depth + 1);
+        let right = self.build(points, mid + 1, hi, depth + 1);
+
+        let node = self.nodes[mid];
+        self.bounds[mid] = Bounds::new(points[node], node).merge(left).merge(right);
+        Some(self.bounds[mid])
+    }
+
+    /// Closest point to `i` with a larger index, ordered by distance then index,
+    /// coming strictly after `after`
+    fn next_neighbour(
+        &self,
+        points: &[Point],
+        i: usize,
+        after: Option<(i128, usize)>,
+    ) -> Option<(i128, usize)> {
+        let mut best = None;
+        self.search(points, (0, self.nodes.len()), 0, i, after, &mut best);
+        best
+    }
+
+    fn search(
+        &self,
+        points: &[Point],
+        (lo, hi): (usize, usize),
+        depth: usize,
+        i: usize,
+        after: Option<(i128, usize)>,
+        best: &mut Option<(i128, usize)>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+        let bounds = self.bounds[mid];
+
+        // Every index in this subtree has already been paired from its own side
+        if bounds.max_index <= i {
+            return;
+        }
+
+        // The whole subtree is either beyond the best so far or already yielded
+        let query = points[i];
+        let (near, far) = bounds.distance_range(query);
+        if best.is_some_and(|b| near > b.0) || after.is_some_and(|a| far < a.0) {
+            return;
+        }
+
+        let j = self.nodes[mid];
+        if j > i {
+            let candidate = (query.squared_distance(points[j]), j);
+            let is_after = after.is_none_or(|a| candidate > a);
+            let is_better = best.is_none_or(|b| candidate < b);
+
+            if is_after && is_better {
+                *best = Some(candidate);
+            }
+        }
+
+        let (first, second) = if query.axis(depth) < points[j].axis(depth) {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+
+        self.search(points, first, depth + 1, i, after, best);
+        self.search(points, second, depth + 1, i, after, best);
+    }
+}
+
+/// Yields every pair in order of distance, holding at most one pending pair per point
+struct PointHeapIter {
+    points: Vec<Point>,
+    tree: KdTree,
+    heap: BinaryHeap<PointPair>,
+}
 
 impl Iterator for PointHeapIter {
     type Item = PointPair;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.pop()
+        let pair = self.heap.pop()?;
+
+        // Replace it with the next closest partner of the same point
+        if let Some((_, j)) =
+            self.tree
+                .next_neighbour(&self.points, pair.i1, Some((pair.d, pair.i2)))
+        {
+            self.heap.push(PointPair::new(&self.points, pair.i1, j));
+        }
+
+        Some(pair)
     }
 }
 
 impl PointHeapIter {
     fn new(points: Vec<Point>) -> Self {
-        let len = points.len();
+        let tree = KdTree::new(&points);
 
-        let mut pairs = BinaryHeap::new();
-        let mut seen_pairs = vec![vec![false; len]; len];
+        let heap = (0..points.len())
+            .filter_map(|i| {
+                tree.next_neighbour(&points, i, None)
+                    .map(|(_, j)| PointPair::new(&points, i, j))
+            })
+            .collect();
 
-        for i in 0..points.len() {
-            for j in 0..points.len() {
-                if i == j || (seen_pairs[i][j] && seen_pairs[j][i]) {
-                    continue;
-                }
+        Self { points, tree, heap }
+    }
+}
 
-                pairs.push(PointPair::new(points[i], points[j]));
+/// Disjoint set forest over point indices, with path compression and union by size
+struct DisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    /// Number of components of each size
+    sizes: BTreeMap<usize, usize>,
+}
 
-                seen_pairs[i][j] = true;
-                seen_pairs[j][i] = true;
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+            sizes: BTreeMap::from([(1, len)]),
+        }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        while self.parent[x] != root {
+            x = std::mem::replace(&mut self.parent[x], root);
+        }
+
+        root
+    }
+
+    /// Merge the components holding `a` and `b`. False if they were already one component.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+
+        if a == b {
+            return false;
+        }
+
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+
+        for s in [self.size[a], self.size[b]] {
+            let count = self.sizes.get_mut(&s).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.sizes.remove(&s);
             }
         }
 
-        Self(pairs)
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        *self.sizes.entry(self.size[a]).or_insert(0) += 1;
+
+        true
+    }
+
+    fn component_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    fn components(&self) -> usize {
+        self.sizes.values().sum()
+    }
+
+    /// Size of every component, largest first
+    fn component_sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.sizes
+            .iter()
+            .rev()
+            .flat_map(|(&size, &count)| std::iter::repeat_n(size, count))
+    }
+}
+
+/// A pair that joined two circuits, i.e. one edge of the minimum spanning tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Merge {
+    /// Number of pairs considered up to and including this one
+    step: usize,
+    p1: Point,
+    p2: Point,
+    i1: usize,
+    i2: usize,
+    /// Squared distance between `p1` and `p2`
+    d: i128,
+    /// Size of the circuit the merge produced
+    size: usize,
+}
+
+impl Merge {
+    fn distance(&self) -> f64 {
+        (self.d as f64).sqrt()
     }
 }
 
 struct Group {
     heap: PointHeapIter,
-    groups: HashMap<Point, u32>,
-    group_id: u32,
-    last_pair: Option<(i32, i32)>,
+    circuits: DisjointSet,
+    step: usize,
+    merges: Vec<Merge>,
 }
 
 impl Group {
     fn new(points: Vec<Point>) -> Self {
         Self {
+            circuits: DisjointSet::new(points.len()),
             heap: PointHeapIter::new(points),
-            groups: HashMap::new(),
-            group_id: 0,
-            last_pair: None,
+            step: 0,
+            merges: Vec::new(),
         }
     }
 
+    /// Connect the next closest pair, false once everything is a single circuit
     fn advance(&mut self) -> bool {
-        let Some(PointPair { p1, p2, d: _ }) = self.heap.next() else {
+        if self.circuits.components() <= 1 {
             return false;
-        };
-
-        let g1 = self.groups.get(&p1);
-        let g2 = self.groups.get(&p2);
-
-        let mut new_link = true;
-
-        match (g1, g2) {
-            (Some(&id1), Some(&id2)) => {
-                if id1 != id2 {
-                    self.groups.iter_mut().for_each(|(_, v)| {
-                        if *v == id1 || *v == id2 {
-                            *v = self.group_id;
-                        }
-                    });
-
-                    self.group_id += 1;
-                    //self.last_pair = Some((p1.0, p2.0));
-                } else {
-                    new_link = false;
-                }
-            }
-            (Some(&g), None) => {
-                self.groups.insert(p2, g);
-                //self.last_pair = Some((p1.0, p2.0));
-            }
-            (None, Some(&g)) => {
-                self.groups.insert(p1, g);
-                //self.last_pair = Some((p1.0, p2.0));
-            }
-            (None, None) => {
-                self.groups.insert(p1, self.group_id);
-                self.groups.insert(p2, self.group_id);
-                self.group_id += 1;
-                //self.last_pair = Some((p1.0, p2.0));
-            }
         }
 
-        if new_link {
-            self.last_pair = Some((p1.0, p2.0));
+        let Some(PointPair { p1, p2, i1, i2, d }) = self.heap.next() else {
+            return false;
+        };
+
+        self.step += 1;
+
+        if self.circuits.union(i1, i2) {
+            self.merges.push(Merge {
+                step: self.step,
+                p1,
+                p2,
+                i1,
+                i2,
+                d,
+                size: self.circuits.component_size(i1),
+            });
         }
 
         true
     }
 
-    fn frequency(&self) -> u32 {
-        let mut frequency = self
-            .groups
-            .values()
-            .fold(HashMap::<u32, u32>::new(), |mut acc, e| {
-                acc.entry(*e).and_modify(|v| *v += 1).or_insert(1);
-                acc
-            })
-            .into_iter()
-            .map(|(_, v)| v)
-            .collect::<Vec<_>>();
-
-        frequency.sort_by(|a, b| a.cmp(b).reverse());
-
-        frequency.into_iter().take(3).product()
+    fn frequency(&self) -> usize {
+        self.circuits.component_sizes().take(3).product()
     }
 
     fn lasts(&self) -> i64 {
-        match self.last_pair {
-            Some((x1, x2)) => x1 as i64 * x2 as i64,
+        match self.merges.last() {
+            Some(m) => m.p1.0 as i64 * m.p2.0 as i64,
             _ => 0,
         }
     }
+
+    /// Part 1 after `check_at` pairs and part 2 once everything is connected. Pairs taken after
+    /// that cannot change any circuit, so part 1 still holds if fewer than `check_at` are needed.
+    fn solve(&mut self, check_at: usize) -> (usize, i64) {
+        while self.step < check_at && self.advance() {}
+        let frequency = self.frequency();
+
+        while self.advance() {}
+
+        (frequency, self.lasts())
+    }
+
+    /// Every merge so far in Kruskal order. Once connected this is the minimum spanning tree.
+    fn spanning_tree(&self) -> &[Merge] {
+        &self.merges
+    }
+
+    /// Step at which the last two circuits were joined
+    fn connected_at(&self) -> Option<usize> {
+        (self.circuits.components() == 1).then(|| self.merges.last().map_or(0, |m| m.step))
+    }
+}
+
+fn write_csv<W: Write>(merges: &[Merge], out: &mut W) -> io::Result<()> {
+    writeln!(out, "step,from,to,squared_distance,distance,circuit_size")?;
+
+    for m in merges {
+        writeln!(
+            out,
+            "{},{},{},{},{:.3},{}",
+            m.step,
+            m.i1,
+            m.i2,
+            m.d,
+            m.distance(),
+            m.size
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_dot<W: Write>(merges: &[Merge], out: &mut W) -> io::Result<()> {
+    let nodes = merges
+        .iter()
+        .flat_map(|m| [(m.i1, m.p1), (m.i2, m.p2)])
+        .collect::<BTreeMap<_, _>>();
+
+    writeln!(out, "graph mst {{")?;
+
+    for (i, Point(x, y, z)) in nodes {
+        writeln!(out, "  {} [label=\"{},{},{}\"];", i, x, y, z)?;
+    }
+
+    for m in merges {
+        writeln!(
+            out,
+            "  {} -- {} [label=\"{:.2}\", step={}];",
+            m.i1,
+            m.i2,
+            m.distance(),
+            m.step
+        )?;
+    }
+
+    writeln!(out, "}}")
 }
 
 fn main() {
@@ -194,19 +492,195 @@ fn main() {
     let points = input
         .split("\n")
         .filter(|l| !l.is_empty())
-        .map(|p| Point::from(p))
+        .map(Point::from)
         .collect::<Vec<_>>();
 
     let mut group = Group::new(points);
-    let mut count = 0;
-    let check_at = 1000;
+    let (frequency, lasts) = group.solve(1000);
+
+    println!("Pt 1: {:?}", frequency);
+    println!("Pt 2: {:?}", lasts);
+
+    let tree = group.spanning_tree();
+    println!("MST Edges: {}", tree.len());
+    println!(
+        "MST Weight: {:.3}",
+        tree.iter().map(Merge::distance).sum::<f64>()
+    );
+    if let Some(step) = group.connected_at() {
+        println!("Connected At Step: {}", step);
+    }
+
+    // cargo run -- [out.csv] [out.dot]
+    for path in std::env::args().skip(1) {
+        let mut file = io::BufWriter::new(fs::File::create(&path).unwrap());
+
+        match path.rsplit_once('.').map(|(_, ext)| ext) {
+            Some("csv") => write_csv(tree, &mut file).unwrap(),
+            Some("dot") => write_dot(tree, &mut file).unwrap(),
+            _ => panic!("Unknown export format {:?}", path),
+        }
+    }
+}
 
-    while group.advance() {
-        count += 1;
-        if count == check_at {
-            println!("Pt 1: {:?}", &group.frequency());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+162,817,812
+57,618,57
+906,360,560
+592,479,940
+352,342,300
+466,668,158
+542,29,236
+431,825,988
+739,650,466
+52,470,668
+216,146,977
+819,987,18
+117,168,530
+805,96,715
+346,949,466
+970,615,88
+941,993,340
+862,61,35
+984,92,344
+425,690,689
+";
+
+    fn sample_points() -> Vec<Point> {
+        SAMPLE.lines().map(Point::from).collect()
+    }
+
+    #[test]
+    fn sample() {
+        let mut group = Group::new(sample_points());
+
+        for _ in 0..10 {
+            group.advance();
         }
+        assert_eq!(group.frequency(), 40);
+
+        while group.advance() {}
+        assert_eq!(group.lasts(), 25272);
+
+        assert_eq!(Group::new(sample_points()).solve(10), (40, 25272));
     }
 
-    println!("Pt 2: {:?}", &group.lasts());
+    #[test]
+    fn connected_before_check() {
+        // Joined into one circuit of 20 after 29 pairs, well short of the check
+        assert_eq!(Group::new(sample_points()).solve(1000), (20, 25272));
+    }
+
+    #[test]
+    fn spanning_tree() {
+        let mut group = Group::new(sample_points());
+        assert_eq!(group.connected_at(), None);
+
+        while group.advance() {}
+
+        let tree = group.spanning_tree();
+        assert_eq!(tree.len(), 19);
+        assert_eq!(group.connected_at(), Some(29));
+        assert_eq!(tree.last().map(|m| m.size), Some(20));
+        assert!(
+            tree.windows(2)
+                .all(|w| w[0].step < w[1].step && w[0].d <= w[1].d)
+        );
+
+        let mut csv = Vec::new();
+        write_csv(tree, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 20);
+        assert_eq!(csv.lines().nth(1), Some("1,0,19,100427,316.902,2"));
+
+        let mut dot = Vec::new();
+        write_dot(tree, &mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert!(dot.starts_with("graph mst {"));
+        assert_eq!(dot.matches(" -- ").count(), 19);
+        assert_eq!(dot.matches("[label=\"").count(), 20 + 19);
+    }
+
+    #[test]
+    fn pairs_in_distance_order() {
+        let points = sample_points();
+        let len = points.len();
+
+        let mut expected = (0..len)
+            .flat_map(|i| ((i + 1)..len).map(move |j| (i, j)))
+            .map(|(i, j)| (points[i].squared_distance(points[j]), i, j))
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        let pairs = PointHeapIter::new(points)
+            .map(|p| (p.d, p.i1, p.i2))
+            .collect::<Vec<_>>();
+
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn ties_in_index_order() {
+        // Unit square, so the four sides and then both diagonals tie
+        let points = vec![
+            Point(0, 0, 0),
+            Point(1, 0, 0),
+            Point(0, 1, 0),
+            Point(1, 1, 0),
+        ];
+
+        let pairs = PointHeapIter::new(points)
+            .map(|p| (p.d, p.i1, p.i2))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            pairs,
+            vec![
+                (1, 0, 1),
+                (1, 0, 2),
+                (1, 1, 3),
+                (1, 2, 3),
+                (2, 0, 3),
+                (2, 1, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn extreme_coordinates() {
+        let min = Point(i32::MIN, i32::MIN, i32::MIN);
+        let max = Point(i32::MAX, i32::MAX, i32::MAX);
+        let side = i128::from(u32::MAX);
+
+        assert_eq!(min.squared_distance(max), 3 * side * side);
+
+        let far = Point(i32::MAX, i32::MAX, 0);
+        let near = Point(i32::MAX, i32::MAX - 1, 0);
+        assert_eq!(
+            min.squared_distance(far) - min.squared_distance(near),
+            2 * side - 1
+        );
+    }
+
+    #[test]
+    fn disjoint_set() {
+        let mut set = DisjointSet::new(6);
+
+        assert!(set.union(0, 1));
+        assert!(set.union(2, 3));
+        assert!(set.union(1, 3));
+        assert!(!set.union(0, 2));
+        assert_eq!(set.component_sizes().collect::<Vec<_>>(), vec![4, 1, 1]);
+
+        assert_eq!(set.components(), 3);
+
+        assert!(set.union(4, 5));
+        assert_eq!(set.component_sizes().collect::<Vec<_>>(), vec![4, 2]);
+        assert_eq!(set.find(5), set.find(4));
+        assert_ne!(set.find(5), set.find(0));
+    }
 }